@@ -1,4 +1,4 @@
 mkdir ergo_cf_release
 cargo build --release
 cp target/release/ergo_cf ergo_cf_release
-echo "http://0.0.0.0:9052" > ergo_cf_release/node.ip
\ No newline at end of file
+touch ergo_cf_release/ergo_cf.portable
\ No newline at end of file
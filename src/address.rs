@@ -0,0 +1,158 @@
+use crate::error::{ErgoCfError, Result};
+use blake2::{Blake2b, Digest};
+use blake2::digest::consts::U32;
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+type Blake2b256 = Blake2b<U32>;
+
+/// Length of the checksum at the end of every address
+const CHECKSUM_LEN : usize = 4;
+/// Length of the compressed public key held by a P2PK address
+const PUBLIC_KEY_LEN : usize = 33;
+
+/// Ergo network which an address (and therefore a Campaign) belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    /// Parses `mainnet` or `testnet`
+    pub fn parse(network: &str) -> Result<Network> {
+        match network.trim().to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(ErgoCfError::InvalidConfig(format!("`{}` is not a valid network, use either `mainnet` or `testnet`.", network))),
+        }
+    }
+
+    /// Network part of the first byte of every address on this network
+    fn prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet => 0x10,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+/// Type of an Ergo address, stored in the low bits of its prefix byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    P2PK,
+    P2SH,
+    P2S,
+}
+
+impl AddressType {
+    fn from_prefix(prefix: u8) -> Option<AddressType> {
+        match prefix & 0x0F {
+            0x01 => Some(AddressType::P2PK),
+            0x02 => Some(AddressType::P2SH),
+            0x03 => Some(AddressType::P2S),
+            _ => None,
+        }
+    }
+}
+
+fn invalid_address(address: &str, reason: &str) -> ErgoCfError {
+    ErgoCfError::InvalidCampaign(format!("Invalid address `{}`: {}", address, reason))
+}
+
+/// Decodes the Base58 address into its bytes
+fn decode_address(address: &str) -> Result<Vec<u8>> {
+    bs58::decode(address).into_vec().map_err(|e| match e {
+        bs58::decode::Error::InvalidCharacter { character, index } => invalid_address(address, &format!("`{}` at position {} is not a Base58 character.", character, index + 1)),
+        _ => invalid_address(address, "it is not valid Base58."),
+    })
+}
+
+/// Reads the network of an address from its prefix byte
+pub fn address_network(address: &str) -> Result<Network> {
+    let bytes = decode_address(address.trim())?;
+    match bytes.first().map(|b| b & 0xF0) {
+        Some(prefix) if prefix == Network::Mainnet.prefix() => Ok(Network::Mainnet),
+        Some(prefix) if prefix == Network::Testnet.prefix() => Ok(Network::Testnet),
+        _ => Err(invalid_address(address, "its prefix does not belong to a known Ergo network.")),
+    }
+}
+
+/// Fully validates an address offline: Base58 encoding, network prefix, address type and Blake2b checksum. P2PK addresses must hold a compressed public key.
+pub fn validate_address(address: &str, network: Network) -> Result<AddressType> {
+    let bytes = decode_address(address)?;
+    if bytes.len() <= 1 + CHECKSUM_LEN {
+        return Err(invalid_address(address, "it is too short."));
+    }
+    check_address_network(address, network)?;
+    let address_type = AddressType::from_prefix(bytes[0])
+        .ok_or_else(|| invalid_address(address, &format!("unknown address type {}.", bytes[0] & 0x0F)))?;
+
+    let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    if Blake2b256::digest(body)[..CHECKSUM_LEN] != *checksum {
+        return Err(invalid_address(address, "the checksum does not match, please check it for typos."));
+    }
+    if address_type == AddressType::P2PK {
+        let public_key = &body[1..];
+        if public_key.len() != PUBLIC_KEY_LEN || (public_key[0] != 0x02 && public_key[0] != 0x03) {
+            return Err(invalid_address(address, "it does not hold a valid compressed public key."));
+        }
+    }
+    Ok(address_type)
+}
+
+/// Validates the address and checks that it is a P2PK address, as required for the creator of a Campaign
+pub fn validate_p2pk_address(address: &str, network: Network) -> Result<()> {
+    match validate_address(address, network)? {
+        AddressType::P2PK => Ok(()),
+        address_type => Err(invalid_address(address, &format!("it is a {:?} address, but a P2PK (wallet) address is required.", address_type))),
+    }
+}
+
+/// Checks that the address belongs to the given network
+pub fn check_address_network(address: &str, network: Network) -> Result<()> {
+    let address_network = address_network(address)?;
+    if address_network != network {
+        return Err(invalid_address(address, &format!("it is a {} address, but {} is in use.", address_network, network)));
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_network_is_valid() {
+        assert_eq!(address_network("9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdf").unwrap(), Network::Mainnet);
+        assert_eq!(address_network("3Wvjj3QVF3PUAgBrrq3Q4oCrS1idUbGNF8uq4xfdbnmSvT1sHEat").unwrap(), Network::Testnet);
+        assert!(address_network("9creator0").is_err());
+        assert!(check_address_network("3Wvjj3QVF3PUAgBrrq3Q4oCrS1idUbGNF8uq4xfdbnmSvT1sHEat", Network::Mainnet).is_err());
+        assert_eq!(Network::parse("Testnet").unwrap(), Network::Testnet);
+        assert!(Network::parse("devnet").is_err());
+    }
+
+    #[test]
+    fn address_validation_is_valid() {
+        assert!(validate_p2pk_address("9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdf", Network::Mainnet).is_ok());
+        assert!(validate_p2pk_address("3Wvjj3QVF3PUAgBrrq3Q4oCrS1idUbGNF8uq4xfdbnmSvT1sHEat", Network::Testnet).is_ok());
+        // Typo in the last character
+        assert!(validate_address("9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdg", Network::Mainnet).is_err());
+        // `l` is not part of the Base58 alphabet
+        assert!(validate_address("9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdl", Network::Mainnet).is_err());
+        assert!(validate_address("3Wvjj3QVF3PUAgBrrq3Q4oCrS1idUbGNF8uq4xfdbnmSvT1sHEat", Network::Mainnet).is_err());
+        assert!(validate_address("9eea", Network::Mainnet).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::error::{ErgoCfError, Result};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::Sum;
+use std::str::FromStr;
+
+/// Number of nanoErg in one Erg
+pub const NANOERGS_PER_ERG : u64 = 1_000_000_000;
+/// Smallest value worth locking in a box. The node charges boxes 360 nanoErg per byte, wallets use 0.001 Erg to cover any simple box.
+pub const MIN_BOX_VALUE : NanoErg = NanoErg(1_000_000);
+/// Number of fractional digits of an Erg amount
+const ERG_DECIMALS : usize = 9;
+
+/// An amount of Erg held as an exact number of nanoErg.
+/// Serialized as an integer of nanoErg. Floats (and decimal strings) are read as Erg, which keeps `.campaign` files from before this type readable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct NanoErg(pub u64);
+
+impl NanoErg {
+    /// Amount of whole Erg
+    pub fn from_erg(erg: u64) -> Result<NanoErg> {
+        erg.checked_mul(NANOERGS_PER_ERG).map(NanoErg).ok_or_else(|| too_large(&erg.to_string()))
+    }
+
+    /// The amount in whole Erg, if it has no fractional part
+    pub fn whole_erg(self) -> Option<u64> {
+        if self.0.is_multiple_of(NANOERGS_PER_ERG) { Some(self.0 / NANOERGS_PER_ERG) } else { None }
+    }
+
+    /// Parses an amount with an optional unit: `12.5` and `12.5 ERG` are Erg, `12500000000 nanoerg` is nanoErg. Units are case insensitive.
+    pub fn parse_amount(amount: &str) -> Result<NanoErg> {
+        let lowercase = amount.trim().to_lowercase();
+        if let Some(value) = lowercase.strip_suffix("nanoergs").or_else(|| lowercase.strip_suffix("nanoerg")) {
+            let value = value.trim();
+            return value.parse().map(NanoErg).map_err(|_| ErgoCfError::InvalidCampaign(format!("`{}` is not a valid whole number of nanoErg.", value)));
+        }
+        match lowercase.strip_suffix("erg") {
+            Some(value) => NanoErg::parse_erg(value),
+            None => NanoErg::parse_erg(&lowercase),
+        }
+    }
+
+    /// Parses a decimal amount of Erg such as `12`, `0.3` or `0.000000001`. At most 9 fractional digits are accepted.
+    pub fn parse_erg(amount: &str) -> Result<NanoErg> {
+        let amount = amount.trim();
+        let invalid = || ErgoCfError::InvalidCampaign(format!("`{}` is not a valid amount of Erg.", amount));
+        let (whole, fraction) = match amount.find('.') {
+            Some(i) => (&amount[..i], &amount[i + 1..]),
+            None => (amount, ""),
+        };
+        if (whole.is_empty() && fraction.is_empty()) || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        if fraction.len() > ERG_DECIMALS {
+            return Err(ErgoCfError::InvalidCampaign(format!("`{}` has more than {} decimal places, the smallest amount is 1 nanoErg (0.000000001 Erg).", amount, ERG_DECIMALS)));
+        }
+        let whole : u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| too_large(amount))? };
+        let fraction : u64 = format!("{:0<width$}", fraction, width = ERG_DECIMALS).parse().map_err(|_| invalid())?;
+        whole.checked_mul(NANOERGS_PER_ERG)
+            .and_then(|n| n.checked_add(fraction))
+            .map(NanoErg)
+            .ok_or_else(|| too_large(amount))
+    }
+
+    /// Adds the amounts, failing if the sum is larger than any possible amount
+    pub fn checked_add(self, other: NanoErg) -> Result<NanoErg> {
+        self.0.checked_add(other.0).map(NanoErg).ok_or_else(|| too_large(&format!("{} + {}", self, other)))
+    }
+
+    /// Adds the amounts, stopping at the largest possible amount
+    pub fn saturating_add(self, other: NanoErg) -> NanoErg {
+        NanoErg(self.0.saturating_add(other.0))
+    }
+
+    /// Subtracts `other`, stopping at zero when it is the larger amount
+    pub fn saturating_sub(self, other: NanoErg) -> NanoErg {
+        NanoErg(self.0.saturating_sub(other.0))
+    }
+
+    /// Converts a legacy float amount of Erg, using its shortest decimal representation so that e.g. `0.3` is exactly 300000000 nanoErg
+    fn from_erg_f64(erg: f64) -> Result<NanoErg> {
+        if !erg.is_finite() || erg < 0_f64 {
+            return Err(ErgoCfError::InvalidCampaign(format!("`{}` is not a valid amount of Erg.", erg)));
+        }
+        let text = format!("{}", erg);
+        match text.find('.') {
+            // Legacy floats can hold more digits than a nanoErg, these are rounded away
+            Some(i) if text.len() - i - 1 > ERG_DECIMALS => NanoErg::parse_erg(&format!("{:.9}", erg)),
+            _ => NanoErg::parse_erg(&text),
+        }
+    }
+}
+
+fn too_large(amount: &str) -> ErgoCfError {
+    ErgoCfError::InvalidCampaign(format!("`{}` Erg is larger than any possible amount.", amount))
+}
+
+impl FromStr for NanoErg {
+    type Err = ErgoCfError;
+
+    fn from_str(amount: &str) -> Result<NanoErg> {
+        NanoErg::parse_erg(amount)
+    }
+}
+
+/// Displays the amount in Erg without trailing zeros, e.g. `12.5`
+impl fmt::Display for NanoErg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let whole = self.0 / NANOERGS_PER_ERG;
+        let fraction = self.0 % NANOERGS_PER_ERG;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let fraction = format!("{:09}", fraction);
+        write!(f, "{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+/// Sums the amounts, stopping at the largest possible amount instead of overflowing
+impl Sum for NanoErg {
+    fn sum<I: Iterator<Item = NanoErg>>(iter: I) -> NanoErg {
+        iter.fold(NanoErg(0), NanoErg::saturating_add)
+    }
+}
+
+impl Serialize for NanoErg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for NanoErg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<NanoErg, D::Error> {
+        deserializer.deserialize_any(NanoErgVisitor)
+    }
+}
+
+struct NanoErgVisitor;
+
+impl<'de> Visitor<'de> for NanoErgVisitor {
+    type Value = NanoErg;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an integer amount of nanoErg or a decimal amount of Erg")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<NanoErg, E> {
+        Ok(NanoErg(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<NanoErg, E> {
+        u64::try_from(v).map(NanoErg).map_err(|_| E::custom("an amount can not be negative"))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<NanoErg, E> {
+        NanoErg::from_erg_f64(v).map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<NanoErg, E> {
+        NanoErg::parse_erg(v).map_err(E::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erg_conv_is_valid() {
+        assert_eq!(NanoErg(1000000000), "1".parse().unwrap());
+        assert_eq!(NanoErg::parse_erg("3.64").unwrap(), NanoErg(3640000000));
+        assert_eq!(NanoErg::parse_erg("0.64").unwrap(), NanoErg(640000000));
+        assert_eq!(NanoErg::parse_erg("0.0064").unwrap(), NanoErg(6400000));
+        assert_eq!(NanoErg::parse_erg("0.000000064").unwrap(), NanoErg(64));
+        assert_eq!(NanoErg::parse_erg("0.000000001").unwrap(), NanoErg(1));
+        assert_eq!(NanoErg::parse_erg("0.3").unwrap(), NanoErg(300000000));
+        assert_eq!(NanoErg::parse_erg(".5").unwrap(), NanoErg(500000000));
+        assert!(NanoErg::parse_erg("0.0000000001").is_err());
+        assert!(NanoErg::parse_erg("-1").is_err());
+        assert!(NanoErg::parse_erg("1e3").is_err());
+        assert!(NanoErg::parse_erg(".").is_err());
+        assert!(NanoErg::parse_erg("99999999999999").is_err());
+        assert_eq!(NanoErg(12500000000).to_string(), "12.5");
+        assert_eq!(NanoErg(1).to_string(), "0.000000001");
+        assert_eq!(NanoErg(2000000000).to_string(), "2");
+        assert_eq!(NanoErg(1).checked_add(NanoErg(2)).unwrap(), NanoErg(3));
+        assert!(NanoErg(u64::MAX).checked_add(NanoErg(1)).is_err());
+        assert_eq!(NanoErg(1).saturating_sub(NanoErg(2)), NanoErg(0));
+        assert_eq!(vec![NanoErg(u64::MAX), NanoErg(1)].into_iter().sum::<NanoErg>(), NanoErg(u64::MAX));
+    }
+
+    #[test]
+    fn amount_units_are_valid() {
+        assert_eq!(NanoErg::parse_amount("12.5").unwrap(), NanoErg(12500000000));
+        assert_eq!(NanoErg::parse_amount("12.5 ERG").unwrap(), NanoErg(12500000000));
+        assert_eq!(NanoErg::parse_amount("12.5erg").unwrap(), NanoErg(12500000000));
+        assert_eq!(NanoErg::parse_amount("12500000000 nanoerg").unwrap(), NanoErg(12500000000));
+        assert_eq!(NanoErg::parse_amount("7 nanoErgs").unwrap(), NanoErg(7));
+        assert!(NanoErg::parse_amount("1.5 nanoerg").is_err());
+        assert!(NanoErg::parse_amount("12.5 sigusd").is_err());
+        assert_eq!(NanoErg(12000000000).whole_erg(), Some(12));
+        assert_eq!(NanoErg(12500000000).whole_erg(), None);
+    }
+
+    #[test]
+    fn nanoerg_serde_is_valid() {
+        let legacy : Vec<NanoErg> = serde_json::from_str("[0.3, 1.0, 3.64, 0.000000001]").unwrap();
+        assert_eq!(legacy, vec![NanoErg(300000000), NanoErg(1000000000), NanoErg(3640000000), NanoErg(1)]);
+        assert_eq!(serde_json::to_string(&NanoErg(300000000)).unwrap(), "300000000");
+        assert_eq!(serde_json::from_str::<NanoErg>("300000000").unwrap(), NanoErg(300000000));
+        assert!(serde_json::from_str::<NanoErg>("-1.5").is_err());
+    }
+}
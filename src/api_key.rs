@@ -1,37 +1,122 @@
-use std::fs::{File};
-use std::io::prelude::*;
+use crate::config::Config;
+use crate::error::{ErgoCfError, Result};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit, OsRng, rand_core::RngCore};
+use sha2::Sha256;
+use std::process::Command;
 
+/// Environment variable holding the passphrase which unlocks the encrypted api key
+pub static PASSPHRASE_ENV : &str = "ERGO_CF_PASSPHRASE";
+const PBKDF2_ROUNDS : u32 = 100_000;
+const SALT_LEN : usize = 16;
+const NONCE_LEN : usize = 12;
 
-/// Saves a provided api key to `api.key` file
-fn save_api_key_to_file(api_key: &String) {
-    let mut file = File::create("api.key").expect("Failed to write to api.key file.");
-    file.write_all(&api_key.clone().into_bytes()).expect("Failed to write api key to file.");
+
+/// Derives the AES-256 key from the passphrase
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// Encrypts the api key with the passphrase. Returns base64 of `salt || nonce || ciphertext`.
+pub fn encrypt_api_key(api_key: &str, passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), api_key.as_bytes())
+        .map_err(|_| ErgoCfError::Storage("Failed to encrypt the api key.".to_string()))?;
+    Ok(base64::encode([&salt[..], &nonce[..], &ciphertext[..]].concat()))
 }
 
-/// Gets an api key from `api.key` file
-fn get_api_key_from_file() -> Option<String> {
-    let mut file = File::open("api.key").ok()?;
-    let mut st = String::new();
-    file.read_to_string(&mut st).ok()?;
-    Some(st.trim().to_string())
+/// Decrypts an api key produced by `encrypt_api_key()`
+pub fn decrypt_api_key(encrypted: &str, passphrase: &str) -> Result<String> {
+    let bytes = base64::decode(encrypted.trim())
+        .map_err(|_| ErgoCfError::Storage("The encrypted api key in the config is corrupted.".to_string()))?;
+    if bytes.len() <= SALT_LEN + NONCE_LEN {
+        return Err(ErgoCfError::Storage("The encrypted api key in the config is corrupted.".to_string()));
+    }
+    let (salt, rest) = bytes.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let key = derive_key(passphrase, salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| ErgoCfError::WrongPassphrase)?;
+    String::from_utf8(plaintext).map_err(|_| ErgoCfError::WrongPassphrase)
 }
 
-/// Tries to get api key from `api.key` file, else asks the user to enter their api key and saves it to `api.key`
-pub fn check_for_api_key() -> String {
-    if let Some(api_key) = get_api_key_from_file(){
-        return api_key;
+/// Runs the external command (e.g. a password manager) which prints the api key to stdout
+fn run_api_key_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
     }
     else {
-        println!("You do not have your node api key saved for use with this CLI app.\nPlease enter it now:");
-        let mut input = String::new();
-        if let Ok(_) = std::io::stdin().read_line(&mut input){
-            println!("API key inputted: {}", input);
-            input.retain(|c| c != '\n' && c != ' ');
-            save_api_key_to_file(&input.trim().to_string());
-        }
-        else {
-            panic!("Provided invalid input. Please relaunch the CLI app and try again with a valid API key.")
-        }
-        return input;
+        Command::new("sh").args(["-c", command]).output()
+    };
+    let output = output.map_err(|e| ErgoCfError::MissingInput(format!("Failed to run the api key command `{}`: {}", command, e)))?;
+    if !output.status.success() {
+        return Err(ErgoCfError::MissingInput(format!("The api key command `{}` failed: {}", command, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Gets the passphrase from the `ERGO_CF_PASSPHRASE` environment variable, if it is set
+pub fn env_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok()
+}
+
+/// Reads the api key from the given environment variable
+pub fn env_api_key(var: &str) -> Result<String> {
+    std::env::var(var)
+        .map(|api_key| api_key.trim().to_string())
+        .map_err(|_| ErgoCfError::MissingInput(format!("The environment variable `{}` holding the api key is not set.", var)))
+}
+
+/// Gets the api key from the first source available:
+/// the `api_key_env` environment variable (either given directly or via the config), the `api_key_command`, the encrypted api key, the plaintext api key in the config.
+/// `passphrase` is only called when the api key has to be decrypted. Returns `None` when no api key is saved.
+pub fn load_api_key<F: FnOnce() -> Result<String>>(api_key_env: Option<&str>, config: &Config, passphrase: F) -> Result<Option<String>> {
+    if let Some(var) = api_key_env.or(config.api_key_env.as_deref()) {
+        return env_api_key(var).map(Some);
+    }
+    if let Some(command) = &config.api_key_command {
+        return run_api_key_command(command).map(Some);
     }
-}
\ No newline at end of file
+    if let Some(encrypted) = &config.api_key_encrypted {
+        return decrypt_api_key(encrypted, &passphrase()?).map(Some);
+    }
+    Ok(config.api_key.clone())
+}
+
+/// Encrypts the api key with the passphrase and saves it into the config file for the given profile (or the top level node settings), removing any plaintext api key
+pub fn save_api_key(api_key: &str, passphrase: &str, profile: Option<&str>) -> Result<()> {
+    let mut stored = Config::from_file()?;
+    stored.set_api_key_encrypted(profile, Some(encrypt_api_key(api_key, passphrase)?))?;
+    stored.save()
+}
+
+/// Removes the saved api key (plaintext or encrypted) of the given profile from the config file
+pub fn clear_api_key(profile: Option<&str>) -> Result<()> {
+    let mut stored = Config::from_file()?;
+    stored.set_api_key_encrypted(profile, None)?;
+    stored.save()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_key_encryption_is_valid() {
+        let encrypted = encrypt_api_key("hello", "passphrase").unwrap();
+        assert!(!encrypted.contains("hello"));
+        assert_ne!(encrypted, encrypt_api_key("hello", "passphrase").unwrap());
+        assert_eq!(decrypt_api_key(&encrypted, "passphrase").unwrap(), "hello");
+        assert!(decrypt_api_key(&encrypted, "wrong").is_err());
+        assert!(decrypt_api_key("AAAA", "passphrase").is_err());
+    }
+}
@@ -1,35 +1,189 @@
-use crate::wallet_reqs::{select_wallet_address, get_p2s_address, send_wallet_payment};
+use crate::address::{Network, validate_p2pk_address};
+use crate::amount::{MIN_BOX_VALUE, NanoErg};
+use crate::deadline::{BLOCK_INTERVAL_SECS, Deadline, estimate_time, format_date_time};
+use crate::error::{ErgoCfError, Result};
+use crate::wallet_reqs::{NodeBox, NodeClient, TX_FEE, TxStatus};
+use chrono::{DateTime, Utc};
 use handlebars::Handlebars;
 use serde::{Serialize, Deserialize};
+use std::convert::TryFrom;
+use std::fmt;
 use std::path::Path;
-use std::fs::{File, read_dir, remove_file};
+use std::fs::{File, copy, create_dir_all, read_dir, remove_dir_all, rename};
 
-static CROWDFUND_TEMPLATE : &'static str = r#"{"source": "{ val backerPubKey = PK(\"{{backer}}\") \n val projectPubKey = PK(\"{{address}}\") \n val deadline = {{deadline}} \n val minToRaise = {{goal}}L * 1000000000 \n val fundraisingFailure = HEIGHT >= deadline && backerPubKey \n val enoughRaised = {(outBox: Box) => outBox.value >= minToRaise && outBox.propositionBytes == projectPubKey.propBytes} \n val fundraisingSuccess = HEIGHT < deadline && projectPubKey && OUTPUTS.exists(enoughRaised) \n fundraisingFailure || fundraisingSuccess }"}"#;
+static CROWDFUND_TEMPLATE : &str = r#"{"source": "{ val backerPubKey = PK(\"{{backer}}\") \n val projectPubKey = PK(\"{{address}}\") \n val deadline = {{deadline}} \n val minToRaise = {{min_to_raise}} \n val fundraisingFailure = HEIGHT >= deadline && backerPubKey \n val enoughRaised = {(outBox: Box) => outBox.value >= minToRaise && outBox.propositionBytes == projectPubKey.propBytes} \n val fundraisingSuccess = HEIGHT < deadline && projectPubKey && OUTPUTS.exists(enoughRaised) \n fundraisingFailure || fundraisingSuccess }"}"#;
 
-#[macro_export]
-macro_rules! STORAGE_FOLDER {() => ( ".storage/" )}
-pub static CAMPAIGNS_FOLDER : &'static str = concat!(STORAGE_FOLDER!(), "campaigns/");
-pub static EXPORT_FOLDER : &'static str = "export/";
+static STORAGE_FOLDER : &str = ".storage/";
+static CAMPAIGNS_FOLDER : &str = ".storage/campaigns/";
+static DATABASE_FILE : &str = ".storage/campaigns.db";
+static EXPORT_FOLDER : &str = "export/";
+/// Extension of the json files holding a campaign
+pub static CAMPAIGN_EXTENSION : &str = ".campaign";
+
+/// Fewest blocks (about an hour) which must remain until the deadline to create, track or back a Campaign without `--force`
+pub const MIN_BLOCKS_UNTIL_DEADLINE : u64 = 30;
+
+/// Path of a folder inside the data dir (the folder which holds local storage and exported campaigns)
+fn data_path(data_dir: &str, folder: &str) -> String {
+    Path::new(data_dir).join(folder).to_string_lossy().to_string()
+}
+
+/// Folder in the data dir holding the locally tracked campaigns
+pub fn campaigns_folder(data_dir: &str) -> String {
+    data_path(data_dir, CAMPAIGNS_FOLDER)
+}
+
+/// Path of the SQLite database in the data dir holding the locally tracked campaigns
+pub fn database_path(data_dir: &str) -> String {
+    data_path(data_dir, DATABASE_FILE)
+}
+
+/// Folder in the data dir holding the exported campaigns
+pub fn export_folder(data_dir: &str) -> String {
+    data_path(data_dir, EXPORT_FOLDER)
+}
+
+/// Builds the folder structure for local storage in the data dir
+pub fn build_folder_structure(data_dir: &str) -> Result<()> {
+    create_dir_all(data_path(data_dir, STORAGE_FOLDER))?;
+    create_dir_all(campaigns_folder(data_dir))?;
+    create_dir_all(export_folder(data_dir))?;
+    Ok(())
+}
+
+/// Moves the local storage and exported campaigns out of `data_dir` into `new_data_dir`, which must not hold any yet
+pub fn move_data_dir(data_dir: &str, new_data_dir: &str) -> Result<()> {
+    let new_dir = Path::new(new_data_dir);
+    // Either dir may not exist yet, in which case only their absolute paths can be compared
+    let same_dir = match (new_dir.canonicalize(), Path::new(data_dir).canonicalize()) {
+        (Ok(new_dir), Ok(data_dir)) => new_dir == data_dir,
+        _ => std::path::absolute(new_dir)? == std::path::absolute(data_dir)?,
+    };
+    if same_dir {
+        return Err(ErgoCfError::Unavailable(format!("`{}` is already the data dir.", new_data_dir)));
+    }
+    for folder in [STORAGE_FOLDER, EXPORT_FOLDER] {
+        if new_dir.join(folder).exists() {
+            return Err(ErgoCfError::Unavailable(format!("`{}` already holds a `{}` folder, please move or remove it first.", new_data_dir, folder.trim_end_matches('/'))));
+        }
+    }
+    create_dir_all(new_dir)?;
+    for folder in [STORAGE_FOLDER, EXPORT_FOLDER] {
+        let from = data_path(data_dir, folder);
+        if Path::new(&from).exists() {
+            move_folder(Path::new(&from), &new_dir.join(folder))?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a folder, copying it when it can not simply be renamed (e.g. onto another file system)
+fn move_folder(from: &Path, to: &Path) -> Result<()> {
+    if rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_folder(from, to)?;
+    remove_dir_all(from)?;
+    Ok(())
+}
+
+fn copy_folder(from: &Path, to: &Path) -> Result<()> {
+    create_dir_all(to)?;
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_folder(&entry.path(), &target)?;
+        }
+        else {
+            copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
 
 
 pub trait CrowdfundingCampaign {
-    fn back_campaign(&self, api_key: &String, amount: f64) -> BackedCampaign;
-    fn build_script(&self, backer_address: &String) -> String;
-    fn export(&self);
-    fn delete(&self);
-    fn save(&self, path: &mut String);
-    fn save_locally(&self);
-    fn print_info(&self);
-    fn print_choice_text(&self, n: u32);
+    fn back_campaign(&self, node: &dyn NodeClient, summary: &BackingSummary) -> Result<BackedCampaign>;
+    fn get_campaign(&self) -> &Campaign;
+    fn as_backed(&self) -> Option<&BackedCampaign>;
+    fn refund_campaign(&self, node: &dyn NodeClient, fee: u64) -> Result<BackedCampaign>;
+    fn collect_campaign(&self, node: &dyn NodeClient, fee: u64) -> Result<Box<dyn CrowdfundingCampaign>>;
+    fn get_status(&self, node: &dyn NodeClient) -> Result<CampaignStatus>;
+    fn build_script(&self, backer_address: &str) -> Result<String>;
+    fn export(&self, folder: &str) -> Result<()>;
+    fn save(&self, path: &mut String) -> Result<()>;
+    fn info(&self) -> String;
+    fn choice_text(&self, n: usize) -> String;
+}
+
+/// The `minToRaise` value of the script. Whole Erg goals keep the original `<erg>L * 1000000000` form so that their P2S addresses match those of earlier versions.
+fn min_to_raise_expression(goal: NanoErg) -> String {
+    match goal.whole_erg() {
+        Some(erg) => format!("{}L * 1000000000", erg),
+        None => format!("{}L", goal.0),
+    }
 }
 
 /// Datatype which holds relevant information about a Crowdfunding Campaign.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "CampaignRecord")]
 pub struct Campaign {
    pub name: String,
    pub address: String,
    pub deadline: u64,
-   pub goal: u64,
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub deadline_target: Option<DateTime<Utc>>,
+   #[serde(rename = "goal_nanoerg")]
+   pub goal: NanoErg,
+   #[serde(default)]
+   pub network: Network,
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub scan_id: Option<u64>,
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub collect_tx_id: Option<String>,
+}
+
+/// A `Campaign` as stored on disk. Files from before goals were stored in nanoErg hold a `goal` of whole Erg instead of `goal_nanoerg`, which is migrated on load.
+#[derive(Deserialize)]
+struct CampaignRecord {
+    name: String,
+    address: String,
+    deadline: u64,
+    #[serde(default)]
+    deadline_target: Option<DateTime<Utc>>,
+    #[serde(default)]
+    goal: Option<u64>,
+    #[serde(default)]
+    goal_nanoerg: Option<NanoErg>,
+    #[serde(default)]
+    network: Network,
+    #[serde(default)]
+    scan_id: Option<u64>,
+    #[serde(default)]
+    collect_tx_id: Option<String>,
+}
+
+impl TryFrom<CampaignRecord> for Campaign {
+    type Error = ErgoCfError;
+
+    fn try_from(record: CampaignRecord) -> Result<Campaign> {
+        let goal = match (record.goal_nanoerg, record.goal) {
+            (Some(goal), _) => goal,
+            (None, Some(legacy_goal)) => NanoErg::from_erg(legacy_goal)?,
+            (None, None) => return Err(ErgoCfError::InvalidCampaign("The campaign has no goal.".to_string())),
+        };
+        Ok(Campaign {
+            name: record.name,
+            address: record.address,
+            deadline: record.deadline,
+            deadline_target: record.deadline_target,
+            goal,
+            network: record.network,
+            scan_id: record.scan_id,
+            collect_tx_id: record.collect_tx_id,
+        })
+    }
 }
 
 /// Datatype which holds a `Campaign` and relevant information about the campaign as a backer. Struct only created after a user has backed a campaign.
@@ -45,238 +199,769 @@ pub struct BackedCampaign {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BackingTx {
     pub tx_id: String,
-    pub backed_amount: f64
+    pub backed_amount: NanoErg,
+    #[serde(default = "default_backing_fee")]
+    pub fee: NanoErg,
+    #[serde(default)]
+    pub refund_tx_id: Option<String>,
+    #[serde(default)]
+    pub status: TxStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked_height: Option<u64>,
+}
+
+/// Datatype which holds what the backer confirms before a backing payment is sent.
+#[derive(Debug, Serialize, Clone)]
+pub struct BackingSummary {
+    pub campaign_name: String,
+    pub backer_address: String,
+    pub p2s_address: String,
+    pub amount: NanoErg,
+    pub fee: NanoErg,
+    pub balance: NanoErg,
+}
+
+/// Backing txs from before fees were recorded were sent with the node's default fee
+fn default_backing_fee() -> NanoErg {
+    NanoErg(TX_FEE)
+}
+
+/// The state of a Campaign derived from the chain height and the funds locked under its script.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CampaignState {
+    Active,
+    Succeeded,
+    Failed,
+    ExpiredUnclaimed,
+}
+
+/// Datatype which holds the on-chain progress of a Campaign at a given height.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignStatus {
+    pub height: u64,
+    pub raised: NanoErg,
+    pub goal: NanoErg,
+    pub blocks_remaining: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_eta: Option<DateTime<Utc>>,
+    pub state: CampaignState,
+    pub backing_txs: Vec<(String, TxStatus)>,
 }
 
 impl Campaign {
-    /// Create a new `Campaign`. Verifies that the deadline and the goal are valid `u64` integers
-    pub fn new (name : &String, address: &String, deadline: &String, goal: &String) -> Campaign{
-        let deadline : u64 = deadline.parse().expect("Deadline provided is not a valid integer.");
-        let goal : u64 = goal.parse().expect("Campaign goal provided is not a valid integer.");
-        Campaign {
-            name: name.clone(),
-            address: address.clone(),
-            deadline: deadline,
-            goal: goal,
-        } 
+    /// Create a new `Campaign`. Verifies that the goal is a valid amount (e.g. `12.5`, `12.5 ERG` or `12500000000 nanoerg`) and that the address is a valid P2PK address of the network
+    pub fn new (name : &str, address: &str, deadline: Deadline, goal: &str, network: Network) -> Result<Campaign> {
+        let address = address.trim();
+        validate_p2pk_address(address, network)?;
+        let goal = NanoErg::parse_amount(goal).map_err(|e| ErgoCfError::InvalidCampaign(format!("Campaign goal provided is not valid: {}", e)))?;
+        Ok(Campaign {
+            name: name.to_string(),
+            address: address.to_string(),
+            deadline: deadline.height,
+            deadline_target: deadline.target,
+            goal,
+            network,
+            scan_id: None,
+            collect_tx_id: None,
+        })
+    }
+
+    /// Create a new `Campaign` from a previously exported `Campaign`. Campaigns exported before networks were recorded are on mainnet.
+    pub fn from_file (path: &str) -> Result<Campaign> {
+        let file = File::open(path)?;
+        let camp : Campaign = serde_json::from_reader(file).map_err(|e| ErgoCfError::InvalidCampaign(format!("Failed to process Campaign from json: {}", e)))?;
+        validate_p2pk_address(&camp.address, camp.network)?;
+        Ok(camp)
+    }
+
+    /// Refuses to act on the Campaign when it belongs to a different network than the one in use
+    pub fn check_network(&self, network: Network) -> Result<()> {
+        if self.network != network {
+            return Err(ErgoCfError::Unavailable(format!("The campaign `{}` is on {}, but your node is configured for {}. Select a {} profile with `--profile <name>`.", self.name, self.network, network, self.network)));
+        }
+        Ok(())
+    }
+
+    /// Checks the Campaign against the current height before it is created, tracked or backed. A deadline which has passed or is fewer than `MIN_BLOCKS_UNTIL_DEADLINE` blocks away is refused, unless `force` is set in which case it becomes a warning. Returns the warnings to show.
+    pub fn check_chain_state(&self, height: u64, force: bool) -> Result<Vec<String>> {
+        let mut warnings = vec![];
+        let blocks_remaining = self.deadline.saturating_sub(height);
+        let problem = if blocks_remaining == 0 {
+            Some(format!("The campaign deadline (block {}) has already passed, the current height is {}. Funds sent to it can only be refunded and never collected.", self.deadline, height))
+        }
+        else if blocks_remaining < MIN_BLOCKS_UNTIL_DEADLINE {
+            Some(format!("The campaign deadline (block {}) is only {} blocks (about {} minutes) away, the current height is {}. Transactions may not be mined before it passes.", self.deadline, blocks_remaining, blocks_remaining as i64 * BLOCK_INTERVAL_SECS / 60, height))
+        }
+        else {
+            None
+        };
+        if let Some(problem) = problem {
+            if !force {
+                return Err(ErgoCfError::Unavailable(format!("{}\nUse `--force` to continue anyway.", problem)));
+            }
+            warnings.push(format!("Warning: {}", problem));
+        }
+        if self.goal < MIN_BOX_VALUE {
+            warnings.push(format!("Warning: The campaign goal of {} Erg is below the minimum box value of {} Erg, so a single backing box will always reach it.", self.goal, MIN_BOX_VALUE));
+        }
+        Ok(warnings)
+    }
+
+    /// Estimated time at which the deadline block will be mined, `None` once it has been reached
+    pub fn deadline_eta(&self, height: u64) -> Option<DateTime<Utc>> {
+        if height < self.deadline { estimate_time(self.deadline - height) } else { None }
+    }
+
+    /// The current height and when the deadline block is estimated to be mined from it, for deadlines given as a height as well as those given as a date/time
+    pub fn deadline_info(&self, height: u64) -> String {
+        match self.deadline_eta(height) {
+            Some(eta) => format!("Current Height: {}\nCampaign Deadline ETA: {} (in {} blocks)", height, format_date_time(&eta), self.deadline - height),
+            None => format!("Current Height: {}\nCampaign Deadline ETA: block {} has been reached", height, self.deadline),
+        }
+    }
+
+    /// Get P2S Address for Backer to submit to for the Campaign
+    pub fn p2s_address(&self, node: &dyn NodeClient, backer_address: &str) -> Result<String> {
+        node.p2s_address(&self.build_script(backer_address)?)
+    }
+
+    /// Builds the `BackingSummary` for backing the Campaign with `amount` from `backer_address`, paying `fee`. Fails when the amount is below the minimum box value or more than the wallet can pay.
+    pub fn backing_summary(&self, node: &dyn NodeClient, backer_address: &str, amount: NanoErg, fee: u64) -> Result<BackingSummary> {
+        check_backing_amount(amount)?;
+        let fee = NanoErg(fee);
+        let balance = NanoErg(node.wallet_balance()?);
+        if amount.checked_add(fee)? > balance {
+            return Err(ErgoCfError::Unavailable(format!("Your wallet balance of {} Erg is not enough to send {} Erg plus the {} Erg tx fee.", balance, amount, fee)));
+        }
+        Ok(BackingSummary {
+            campaign_name: self.name.clone(),
+            backer_address: backer_address.to_string(),
+            p2s_address: self.p2s_address(node, backer_address)?,
+            amount,
+            fee,
+            balance,
+        })
+    }
+
+    /// Sends `amount` Erg from the node wallet to the P2S Address, paying `fee`
+    pub fn send_backing_payment(&self, node: &dyn NodeClient, p2s_address: &str, amount: NanoErg, fee: NanoErg) -> Result<BackingTx> {
+        let tx_id = node.send_payment(p2s_address, amount.0, fee.0)?;
+        Ok(BackingTx::new(tx_id, amount, fee))
     }
 
-    /// Create a new `Campaign` from a previously exported `Campaign`
-    pub fn from_file (path: &String) -> Campaign {
-        let file = File::open(path).expect("Failed to read Campaign file.");
-        serde_json::from_reader(file).expect("Failed to process Campaign from json.")
+    /// Registers a scan on the node for boxes whose script contains the campaign public key, so that backer boxes can be discovered when collecting
+    pub fn register_scan(&mut self, node: &dyn NodeClient) -> Result<()> {
+        let pk_raw = node.address_raw(&self.address)?;
+        self.scan_id = Some(node.register_box_scan(&format!("ergo_cf: {}", self.name), &pk_raw)?);
+        Ok(())
+    }
+
+    /// Finds all unspent boxes locked under this Campaign's crowdfunding script, regardless of which backer created them.
+    /// Fails for a Campaign without a registered scan, as the funds it has raised can not be known.
+    pub fn find_backer_boxes(&self, node: &dyn NodeClient) -> Result<Vec<NodeBox>> {
+        let scan_id = self.scan_id.ok_or_else(|| ErgoCfError::Unavailable("This campaign has no box scan registered on your node, so the funds it has raised are unknown. Only campaigns created with `ergo_cf create` are scanned.".to_string()))?;
+        let reference_p2s = self.p2s_address(node, &self.address)?;
+        let reference_tree = node.address_tree(&reference_p2s)?;
+        Ok(node.scan_unspent_boxes(scan_id)?.into_iter()
+            .filter(|b| matches_campaign_script(&reference_tree, &b.ergo_tree))
+            .collect())
+    }
+
+    /// Builds the `CampaignStatus` of the Campaign from the given unspent backer boxes
+    pub fn status_from_boxes(&self, height: u64, boxes: &[NodeBox]) -> CampaignStatus {
+        let raised : NanoErg = boxes.iter().map(|b| NanoErg(b.value)).sum();
+        let goal = self.goal;
+        CampaignStatus {
+            height,
+            raised,
+            goal,
+            blocks_remaining: self.deadline.saturating_sub(height),
+            deadline_eta: self.deadline_eta(height),
+            state: CampaignState::derive(height, self.deadline, raised.0, goal.0, self.collect_tx_id.is_some()),
+            backing_txs: vec![],
+        }
+    }
+
+    /// Spends all backer boxes into a single output to the campaign address. Returns the updated `Campaign` with the collection tx.
+    pub fn collect(&self, node: &dyn NodeClient, fee: u64) -> Result<Campaign> {
+        if self.scan_id.is_none() {
+            return Err(ErgoCfError::Unavailable("This campaign has no box scan registered on your node. Only campaigns created with `ergo_cf create` can be collected.".to_string()));
+        }
+        if !node.wallet_addresses()?.contains(&self.address) {
+            return Err(ErgoCfError::Unavailable(format!("The campaign address {} is not part of your node wallet. Only the creator of a campaign can collect its funds.", self.address)));
+        }
+        let height = node.current_height()?;
+        if height >= self.deadline {
+            return Err(ErgoCfError::Unavailable(format!("The campaign deadline (block {}) has passed, the current height is {}.\nThe funds can no longer be collected and are now refundable by the backers.", self.deadline, height)));
+        }
+
+        let boxes = self.find_backer_boxes(node)?;
+        let total : NanoErg = boxes.iter().map(|b| NanoErg(b.value)).sum();
+        let fee = NanoErg(fee);
+        if total < self.goal.checked_add(fee)? {
+            return Err(ErgoCfError::Unavailable(format!("The campaign has raised {} Erg so far, which is not enough to reach the goal of {} Erg (plus the {} Erg tx fee).", total, self.goal, fee)));
+        }
+
+        let inputs_raw = boxes.iter().map(|b| node.box_bytes(&b.box_id)).collect::<Result<Vec<String>>>()?;
+        let collect_tx_id = node.send_transaction(&self.address, total.saturating_sub(fee).0, fee.0, &inputs_raw)?;
+        let mut camp = self.clone();
+        camp.collect_tx_id = Some(collect_tx_id);
+        Ok(camp)
     }
 }
 
 impl BackedCampaign {
     /// Create a new `BackedCampaign`. 
     pub fn new (campaign : Campaign, backer_address: String, p2s_address: String, backer_txs: Vec<BackingTx>) -> BackedCampaign { 
-        BackedCampaign  {   campaign: campaign,
-                            backer_address: backer_address,
-                            p2s_address: p2s_address,
-                            backer_txs: backer_txs
+        BackedCampaign  {   campaign,
+                            backer_address,
+                            p2s_address,
+                            backer_txs
                         }
     }
+
+    /// Checks that the campaign is backed again from the same address, as the P2S address (and so the refund of every `BackingTx`) depends on it
+    pub fn check_backer_address(&self, backer_address: &str) -> Result<()> {
+        if backer_address != self.backer_address {
+            return Err(ErgoCfError::InvalidCampaign(format!("You backed this campaign from {} before. Please back it from the same address so that a single refund covers all of your backing txs.", self.backer_address)));
+        }
+        Ok(())
+    }
+
+    /// Refreshes the confirmation state of every `BackingTx` from the node
+    pub fn refresh_txs(&self, node: &dyn NodeClient) -> Result<BackedCampaign> {
+        let height = node.current_height()?;
+        let mut backed_camp = self.clone();
+        for tx in backed_camp.backer_txs.iter_mut() {
+            tx.status = node.tx_status(&tx.tx_id)?;
+            tx.checked_height = Some(height);
+        }
+        Ok(backed_camp)
+    }
 }
 
 
 impl CrowdfundingCampaign for Campaign {
     /// Builds the crowdfunding script with the required fields filled in
-    fn build_script(&self, backer_address: &String) -> String {
+    fn build_script(&self, backer_address: &str) -> Result<String> {
         let reg = Handlebars::new();
         let finalized_script = reg.render_template(CROWDFUND_TEMPLATE, 
         &json!({"backer": backer_address
             ,"address": self.address
             ,"deadline": self.deadline.to_string()
-            ,"goal": self.goal.to_string()
+            ,"min_to_raise": min_to_raise_expression(self.goal)
         }));
 
-        finalized_script.expect("Failed to produce crowdfunding script.")
+        finalized_script.map_err(|e| ErgoCfError::InvalidCampaign(format!("Failed to produce crowdfunding script: {}", e)))
     }
 
     /// Saves `Campaign` to path
-    fn save(&self, path: &mut String) {
-        path.push_str(&self.name);
-        path.push_str(".campaign");
-        clean_path_name(path);
-        let file = File::create(path.trim()).expect("Failed to create Campaign file.");
-        serde_json::to_writer_pretty(file, &self).expect("Failed to save Campaign to file.");
+    fn save(&self, path: &mut String) -> Result<()> {
+        *path = campaign_file_path(path, &self.name, CAMPAIGN_EXTENSION);
+        let file = File::create(path.trim())?;
+        serde_json::to_writer_pretty(file, &self).map_err(|e| ErgoCfError::Storage(e.to_string()))
     }
 
-    /// Save the `Campaign` locally into a json file in the Campaigns folder
-    fn save_locally(&self) {
-        let mut path = CAMPAIGNS_FOLDER.to_string();
-        self.save(&mut path);
+    /// Exports the `Campaign` into a json file to be shared in the given folder (see `export_folder()`). The node specific scan id is left out.
+    fn export(&self, folder: &str) -> Result<()> {
+        let mut path = folder.to_string();
+        let mut camp = self.clone();
+        camp.scan_id = None;
+        camp.save(&mut path)
     }
 
-    /// Exports the `Campaign` into a json file to be shared in the export folder
-    fn export(&self) {
-        let mut path = EXPORT_FOLDER.to_string();
-        self.save(&mut path);
+    /// Allows the user to back the Campaign as confirmed in the `BackingSummary`
+    fn back_campaign(&self, node: &dyn NodeClient, summary: &BackingSummary) -> Result<BackedCampaign> {
+        let backing_tx = self.send_backing_payment(node, &summary.p2s_address, summary.amount, summary.fee)?;
+
+        let backed_camp = BackedCampaign::new(self.clone(), summary.backer_address.clone(), summary.p2s_address.clone(), vec![backing_tx]);
+        Ok(backed_camp)
     }
 
-    /// Deletes the tracked Campaign from local storage
-    fn delete (&self) {
-        let mut path = CAMPAIGNS_FOLDER.to_string();
-        path.push_str(&self.name);
-        path.push_str(".campaign");
-        clean_path_name(&mut path);
-        remove_file(path).ok();
+    /// A `Campaign` which was never backed has no funds locked to refund
+    fn refund_campaign(&self, _node: &dyn NodeClient, _fee: u64) -> Result<BackedCampaign> {
+        Err(ErgoCfError::Unavailable("You have not backed this campaign, so there are no funds to refund.".to_string()))
     }
 
-    /// Allows the user to back the Campaign
-    fn back_campaign(&self, api_key: &String, amount: f64) -> BackedCampaign {
-        let backer_address = select_wallet_address(&api_key);
-        let p2s_address = get_p2s_address(&api_key, &self, &backer_address);
-        let backing_tx = send_wallet_payment(&api_key, &p2s_address, amount);
+    /// Collects the funds of the Campaign if it has reached its goal before the deadline
+    fn collect_campaign(&self, node: &dyn NodeClient, fee: u64) -> Result<Box<dyn CrowdfundingCampaign>> {
+        let camp = self.collect(node, fee)?;
+        Ok(Box::new(camp))
+    }
+
+    /// Gets the on-chain status of the Campaign using the boxes found by its scan
+    fn get_status(&self, node: &dyn NodeClient) -> Result<CampaignStatus> {
+        let height = node.current_height()?;
+        let boxes = self.find_backer_boxes(node)?;
+        Ok(self.status_from_boxes(height, &boxes))
+    }
 
-        if let Some(bt) = backing_tx {
-            let backer_txs = vec![bt];
-            let new_camp = Campaign::new(&self.name.clone(), &self.address.clone(), &self.deadline.to_string(), &self.goal.to_string());
-            let backed_camp = BackedCampaign::new(new_camp, backer_address, p2s_address, backer_txs);
-            backed_camp.clone().save_locally();
-            return backed_camp;
+    /// Info about the Campaign
+    fn info(&self) -> String {
+        let mut info = format!("Campaign Name: {}\nCampaign Address: {}\nCampaign Network: {}\nCampaign Deadline Block: {}\nCampaign Goal: {} Erg", self.name, self.address, self.network, self.deadline, self.goal);
+        if let Some(target) = &self.deadline_target {
+            info += &format!("\nCampaign Deadline Target: {} (estimated at creation)", format_date_time(target));
         }
-        panic!("Failed to send wallet payment to P2S Address.");
+        if let Some(collect_tx_id) = &self.collect_tx_id {
+            info += &format!("\nFunds Collected In Tx: {}", collect_tx_id);
+        }
+        info
     }
 
-    /// Prints info about the Campaign
-    fn print_info(&self) {
-        println!("Campaign Name: {}\nCampaign Address: {}\nCampaign Deadline Block: {}\nCampaign Goal: {}", self.name, self.address, self.deadline, self.goal);
+    fn choice_text(&self, n: usize) -> String {
+        format!("{}. {}", n, self.name)
     }
 
-    fn print_choice_text(&self, n: u32) {
-        println!("{}. {}", n, self.name);
+    fn get_campaign(&self) -> &Campaign {
+        self
+    }
 
+    fn as_backed(&self) -> Option<&BackedCampaign> {
+        None
     }
 }
 
 
 impl CrowdfundingCampaign for BackedCampaign {
 
-    fn print_choice_text(&self, n: u32) {
-        println!("{}. {} - (You Backed This Campaign Previously)", n, self.campaign.name);
+    fn choice_text(&self, n: usize) -> String {
+        format!("{}. {} - (You Backed This Campaign Previously)", n, self.campaign.name)
     }
 
-    fn build_script(&self, backer_address: &String) -> String {
-        self.campaign.build_script(backer_address)
+    fn get_campaign(&self) -> &Campaign {
+        &self.campaign
     }
 
-    /// Saves the `BackedCampaign` to path
-    fn save(&self, path: &mut String) {
-        path.push_str(&self.campaign.name);
-        path.push_str(".campaign");
-        clean_path_name(path);
-        let file = File::create(path.trim()).expect("Failed to create Backed Campaign file.");
-        serde_json::to_writer_pretty(file, &self).expect("Failed to save Backed Campaign to file.");
-        println!("Campaign saved locally.");
+    fn as_backed(&self) -> Option<&BackedCampaign> {
+        Some(self)
     }
 
-    /// Save the `BackedCampaign` locally into a json file in the Campaigns folder
-    fn save_locally(&self) {
-        let mut path = CAMPAIGNS_FOLDER.to_string();
-        self.save(&mut path);
+    fn build_script(&self, backer_address: &str) -> Result<String> {
+        self.campaign.build_script(backer_address)
     }
 
-    /// Exports the `Campaign` from the `BackedCampaign` to Export folder
-    fn export(&self) {
-        self.campaign.export();
+    /// Saves the `BackedCampaign` to path
+    fn save(&self, path: &mut String) -> Result<()> {
+        *path = campaign_file_path(path, &self.campaign.name, CAMPAIGN_EXTENSION);
+        let file = File::create(path.trim())?;
+        serde_json::to_writer_pretty(file, &self).map_err(|e| ErgoCfError::Storage(e.to_string()))
     }
 
-    /// Deletes the tracked Campaign from local storage
-    fn delete (&self) {
-        self.campaign.delete();
+    /// Exports the `Campaign` from the `BackedCampaign` to Export folder
+    fn export(&self, folder: &str) -> Result<()> {
+        self.campaign.export(folder)
     }
 
     // Allow the backer to back the same Campaign again. Creates a new `BackedCampaign` with the new `BackingTx` produced from the new `send_wallet_payment()` added to `backer_txs` vector.
-    fn back_campaign(&self, api_key: &String, amount: f64) -> BackedCampaign {
-        let backer_address = select_wallet_address(&api_key);
-        let p2s_address = get_p2s_address(&api_key, &self.campaign, &backer_address);
-        let backing_tx = send_wallet_payment(&api_key, &p2s_address, amount);
+    fn back_campaign(&self, node: &dyn NodeClient, summary: &BackingSummary) -> Result<BackedCampaign> {
+        self.check_backer_address(&summary.backer_address)?;
+        let backing_tx = self.campaign.send_backing_payment(node, &summary.p2s_address, summary.amount, summary.fee)?;
+
+        let mut backer_txs = self.backer_txs.clone();
+        backer_txs.push(backing_tx);
+        let backed_camp = BackedCampaign::new(self.campaign.clone(), summary.backer_address.clone(), summary.p2s_address.clone(), backer_txs);
+        Ok(backed_camp)
+    }
 
-        if let Some(bt) = backing_tx {
-            let mut backer_txs = self.backer_txs.clone();
-            backer_txs.push(bt);
-            let backed_camp = BackedCampaign::new(self.campaign.clone(), backer_address, p2s_address, backer_txs);
-            backed_camp.clone().save_locally();
-            return backed_camp;
+    /// Spends all boxes locked at the P2S address back to the backer address once the deadline has passed. Records the refund tx against the `BackingTx`s whose boxes were spent.
+    fn refund_campaign(&self, node: &dyn NodeClient, fee: u64) -> Result<BackedCampaign> {
+        let height = node.current_height()?;
+        if height < self.campaign.deadline {
+            return Err(ErgoCfError::Unavailable(format!("The campaign deadline (block {}) has not been reached yet, the current height is {}.\nFunds can only be refunded once the deadline has passed.", self.campaign.deadline, height)));
+        }
+
+        let boxes = node.unspent_boxes(&self.p2s_address)?;
+        let total : NanoErg = boxes.iter().map(|b| NanoErg(b.value)).sum();
+        let fee = NanoErg(fee);
+        if boxes.is_empty() {
+            return Err(ErgoCfError::Unavailable("There are no unspent boxes locked at the P2S address. Your funds have already been refunded or collected.".to_string()));
+        }
+        if total <= fee {
+            return Err(ErgoCfError::Unavailable(format!("The {} Erg locked at the P2S address is not enough to cover the transaction fee.", total)));
         }
-        panic!("Failed to send wallet payment to P2S Address.");
 
+        let inputs_raw = boxes.iter().map(|b| node.box_bytes(&b.box_id)).collect::<Result<Vec<String>>>()?;
+        let refund_tx_id = node.send_transaction(&self.backer_address, total.saturating_sub(fee).0, fee.0, &inputs_raw)?;
+        let mut backed_camp = self.clone();
+        for tx in backed_camp.backer_txs.iter_mut() {
+            if boxes.iter().any(|b| b.transaction_id == tx.tx_id) {
+                tx.refund_tx_id = Some(refund_tx_id.clone());
+            }
+        }
+        Ok(backed_camp)
+    }
+
+    /// Collects the funds of the Campaign, keeping the backing information intact
+    fn collect_campaign(&self, node: &dyn NodeClient, fee: u64) -> Result<Box<dyn CrowdfundingCampaign>> {
+        let camp = self.campaign.collect(node, fee)?;
+        let backed_camp = BackedCampaign::new(camp, self.backer_address.clone(), self.p2s_address.clone(), self.backer_txs.clone());
+        Ok(Box::new(backed_camp))
     }
 
-    /// Prints info about the `BackedCampaign`
-    fn print_info(&self) {
-        self.campaign.print_info();
-        println!("Address You Used To Back: {}\nP2S Address Paid To: {}\nBacking Txs:", self.backer_address, self.p2s_address);
+    /// Gets the on-chain status of the Campaign using the boxes at the known P2S address together with those found by the campaign scan
+    fn get_status(&self, node: &dyn NodeClient) -> Result<CampaignStatus> {
+        let height = node.current_height()?;
+        let mut boxes = self.campaign.find_backer_boxes(node)?;
+        for b in node.unspent_boxes(&self.p2s_address)? {
+            if !boxes.iter().any(|known| known.box_id == b.box_id) {
+                boxes.push(b);
+            }
+        }
+        let mut status = self.campaign.status_from_boxes(height, &boxes);
+        for tx in &self.backer_txs {
+            status.backing_txs.push((tx.tx_id.clone(), node.tx_status(&tx.tx_id)?));
+        }
+        Ok(status)
+    }
+
+    /// Info about the `BackedCampaign`
+    fn info(&self) -> String {
+        let mut info = self.campaign.info();
+        info += &format!("\nAddress You Used To Back: {}\nP2S Address Paid To: {}\nBacking Txs:", self.backer_address, self.p2s_address);
         for tx in &self.backer_txs{
-            println!("   - {}: {} Erg", tx.tx_id, tx.backed_amount);
+            let confirmation = match tx.checked_height {
+                Some(height) => describe_tx_status(&tx.status, height),
+                None => "not checked yet".to_string(),
+            };
+            match &tx.refund_tx_id {
+                Some(refund_tx_id) => info += &format!("\n   - {}: {} Erg + {} Erg fee, {} (Refunded in {})", tx.tx_id, tx.backed_amount, tx.fee, confirmation, refund_tx_id),
+                None => info += &format!("\n   - {}: {} Erg + {} Erg fee, {}", tx.tx_id, tx.backed_amount, tx.fee, confirmation),
+            }
         }
+        let backed : NanoErg = self.backer_txs.iter().map(|tx| tx.backed_amount).sum();
+        let fees : NanoErg = self.backer_txs.iter().map(|tx| tx.fee).sum();
+        info += &format!("\nTotal Backed: {} Erg ({} Erg including fees)", backed, backed.saturating_add(fees));
+        info
     }
 }
 
-impl BackingTx {
-    pub fn new(tx_id: String, backed_amount: f64) -> BackingTx {
-        BackingTx {tx_id: tx_id, backed_amount: backed_amount}
+impl CampaignState {
+    /// Derives the state of a Campaign. A goal which was reached but never collected before the deadline leaves the funds refundable only.
+    pub fn derive(height: u64, deadline: u64, raised: u64, goal: u64, collected: bool) -> CampaignState {
+        if collected || (height < deadline && raised >= goal) {
+            CampaignState::Succeeded
+        }
+        else if height < deadline {
+            CampaignState::Active
+        }
+        else if raised >= goal {
+            CampaignState::ExpiredUnclaimed
+        }
+        else {
+            CampaignState::Failed
+        }
     }
 }
 
-/// Choose a campaign from those which are locally saved
-pub fn choose_local_campaign(action_string: &String) -> Box<CrowdfundingCampaign> {
-    let mut camps = get_local_campaigns();
-    if camps.len() == 0 {
-        println!("You have no local Campaigns. Please create or track a Campaign first to interact with one."); 
-        std::process::exit(0);
+impl fmt::Display for CampaignState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            CampaignState::Active => "Active",
+            CampaignState::Succeeded => "Succeeded",
+            CampaignState::Failed => "Failed",
+            CampaignState::ExpiredUnclaimed => "Expired (goal reached but never collected)",
+        };
+        write!(f, "{}", text)
     }
-    let mut n = 0;
-    for camp in &camps {
-        n += 1;
-        camp.print_choice_text(n);
+}
+
+impl CampaignStatus {
+    /// Info about the on-chain progress of the Campaign
+    pub fn info(&self) -> String {
+        let percentage = if self.goal.0 == 0 { 100_f64 } else { self.raised.0 as f64 * 100_f64 / self.goal.0 as f64 };
+        let mut info = format!("Current Height: {}\nRaised: {} / {} Erg ({:.2}%)\nBlocks Remaining Until Deadline: {}", self.height, self.raised, self.goal, percentage, self.blocks_remaining);
+        if let Some(eta) = &self.deadline_eta {
+            info += &format!("\nDeadline ETA: {}", format_date_time(eta));
+        }
+        info += &format!("\nState: {}", self.state);
+        if !self.backing_txs.is_empty() {
+            info += "\nBacking Tx Confirmations:";
+        }
+        for (tx_id, tx_status) in &self.backing_txs {
+            info += &format!("\n   - {}: {}", tx_id, describe_tx_status(tx_status, self.height));
+        }
+        info
     }
+}
 
-    // Making the campaign selection text more context dependent.
-    println!("\nWhich campaign would you like to {}?", action_string);
+impl BackingSummary {
+    /// Wallet balance left once the payment and its fee have been sent
+    pub fn balance_after(&self) -> NanoErg {
+        self.balance.saturating_sub(self.amount).saturating_sub(self.fee)
+    }
 
+    /// Summary of the backing payment
+    pub fn info(&self) -> String {
+        format!("Campaign: {}\nBacker Address: {}\nP2S Address: {}\nAmount: {} Erg\nFee: {} Erg\nWallet Balance After: {} Erg", self.campaign_name, self.backer_address, self.p2s_address, self.amount, self.fee, self.balance_after())
+    }
+}
 
-    // Get Campaign based on choice
-    let mut input = String::new();
-    if let Ok(_) = std::io::stdin().read_line(&mut input){
-        if let Ok(input_n) = input.trim().parse::<usize>(){
-            if input_n > get_local_campaigns().len() || input_n < 1 {
-                println!("Please select a campaign within the range.");
-                return choose_local_campaign(action_string);
-            }
-            return camps.remove(input_n-1);
-        }
-    }
-    return choose_local_campaign(action_string);
-}
-
-/// Get a vector of the locally stored `Campaign`s and `BackedCampaign`s
-pub fn get_local_campaigns() -> Vec<Box<CrowdfundingCampaign>> {
-    let mut campaigns : Vec<Box<CrowdfundingCampaign>> = vec![];
-    let path = Path::new(CAMPAIGNS_FOLDER);
-    if let Ok(rd) = read_dir(path){
-        for rentry in rd {
-            if let Ok(entry) = rentry {
-                let file = File::open(entry.path()).expect("Failed to read Campaign json file.");
-                let campaign : Option<Campaign> =  serde_json::from_reader(file).ok();
-                if let Some(camp) = campaign {
-                    campaigns.push(Box::new(camp));
-                    continue;
-                }
-                let file = File::open(entry.path()).expect("Failed to read Campaign json file.");
-                let backedcampaign : Option<BackedCampaign> = serde_json::from_reader(file).ok();
-                if let Some(backed_camp) = backedcampaign {
-                    campaigns.push(Box::new(backed_camp));
-                }
-            }
-        }
+impl BackingTx {
+    pub fn new(tx_id: String, backed_amount: NanoErg, fee: NanoErg) -> BackingTx {
+        BackingTx {tx_id, backed_amount, fee, refund_tx_id: None, status: TxStatus::Pending, checked_height: None}
     }
-    campaigns
 }
 
+/// Describes the confirmation state of a transaction at the given height
+fn describe_tx_status(tx_status: &TxStatus, height: u64) -> String {
+    match tx_status {
+        TxStatus::Confirmed(included) => format!("{} confirmations (included at block {})", (height + 1).saturating_sub(*included), included),
+        TxStatus::Pending => "pending in mempool".to_string(),
+        TxStatus::Dropped => "dropped (neither mined nor in the mempool)".to_string(),
+    }
+}
+
+/// Checks that an amount is worth backing a Campaign with, i.e. at least the minimum box value
+pub fn check_backing_amount(amount: NanoErg) -> Result<NanoErg> {
+    if amount < MIN_BOX_VALUE {
+        return Err(ErgoCfError::InvalidCampaign(format!("{} Erg is below the minimum box value, please send at least {} Erg.", amount, MIN_BOX_VALUE)));
+    }
+    Ok(amount)
+}
+
+/// Finds the campaign matching the `selection`, either its number in the list (starting at 1) or else its name.
+/// A number always picks from the list, even if a campaign is named like a number.
+pub fn find_campaign(mut camps: Vec<Box<dyn CrowdfundingCampaign>>, selection: &str) -> Result<Box<dyn CrowdfundingCampaign>> {
+    match selection.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= camps.len() => return Ok(camps.remove(n - 1)),
+        _ => (),
+    }
+    match camps.iter().position(|c| c.get_campaign().name == selection) {
+        Some(i) => Ok(camps.remove(i)),
+        None => Err(ErgoCfError::InvalidCampaign(format!("No tracked campaign matches `{}`.", selection))),
+    }
+}
+
+/// Path of the file named after the campaign with the given extension (e.g. `.campaign`) in the folder
+pub(crate) fn campaign_file_path(folder: &str, name: &str, extension: &str) -> String {
+    let mut file_name = format!("{}{}", name, extension);
+    clean_path_name(&mut file_name);
+    format!("{}{}", folder, file_name)
+}
 
 fn clean_path_name (path: &mut String) {
     path.retain(|c| c != '\n' && c != ' ')
-}
\ No newline at end of file
+}
+
+/// Checks whether `ergo_tree` is the same crowdfunding script as `reference_tree`, differing at most in the 33 bytes of the embedded backer public key
+fn matches_campaign_script(reference_tree: &str, ergo_tree: &str) -> bool {
+    if reference_tree.len() != ergo_tree.len() {
+        return false;
+    }
+    let differing : Vec<usize> = reference_tree.bytes().zip(ergo_tree.bytes())
+        .enumerate()
+        .filter(|(_, (r, e))| r != e)
+        .map(|(i, _)| i)
+        .collect();
+    match (differing.first(), differing.last()) {
+        (Some(first), Some(last)) => last - first < 66,
+        _ => true
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const CREATOR : &str = "9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdf";
+    const BACKER : &str = "9giqagqgA7ix97nmFUhrty2smh7gisuEudHZgkT6o1QAUiRauCe";
+
+    /// A transaction sent through the `MockNodeClient`: `(address, value, fee, inputs_raw)`
+    type SentTx = (String, u64, u64, Vec<String>);
+
+    /// `NodeClient` serving fixed chain state without any http requests. Transactions sent through it are recorded in `sent`.
+    #[derive(Default)]
+    struct MockNodeClient {
+        height: u64,
+        p2s_boxes: Vec<NodeBox>,
+        scan_boxes: Vec<NodeBox>,
+        wallet: Vec<String>,
+        sent: RefCell<Vec<SentTx>>,
+    }
+
+    impl NodeClient for MockNodeClient {
+        fn wallet_addresses(&self) -> Result<Vec<String>> { Ok(self.wallet.clone()) }
+        fn p2s_address(&self, _script: &str) -> Result<String> { Ok("p2s".to_string()) }
+        fn wallet_balance(&self) -> Result<u64> { Ok(3000000000) }
+        fn send_payment(&self, _address: &str, _value: u64, _fee: u64) -> Result<String> { Ok("tx".to_string()) }
+        fn send_transaction(&self, address: &str, value: u64, fee: u64, inputs_raw: &[String]) -> Result<String> {
+            self.sent.borrow_mut().push((address.to_string(), value, fee, inputs_raw.to_vec()));
+            Ok("tx".to_string())
+        }
+        fn current_height(&self) -> Result<u64> { Ok(self.height) }
+        fn unspent_boxes(&self, _address: &str) -> Result<Vec<NodeBox>> { Ok(self.p2s_boxes.clone()) }
+        fn box_bytes(&self, box_id: &str) -> Result<String> { Ok(box_id.to_string()) }
+        fn address_raw(&self, _address: &str) -> Result<String> { Ok("02aa".to_string()) }
+        fn address_tree(&self, _address: &str) -> Result<String> { Ok("1000".to_string()) }
+        fn register_box_scan(&self, _scan_name: &str, _pk_raw: &str) -> Result<u64> { Ok(1) }
+        fn scan_unspent_boxes(&self, _scan_id: u64) -> Result<Vec<NodeBox>> { Ok(self.scan_boxes.clone()) }
+        fn tx_status(&self, tx_id: &str) -> Result<TxStatus> {
+            if tx_id == "backing" { Ok(TxStatus::Confirmed(self.height - 2)) } else { Ok(TxStatus::Dropped) }
+        }
+    }
+
+    fn node_box(box_id: &str, value: u64, ergo_tree: &str) -> NodeBox {
+        NodeBox { box_id: box_id.to_string(), value, ergo_tree: ergo_tree.to_string(), transaction_id: "backing".to_string() }
+    }
+
+    #[test]
+    fn backed_campaign_status_is_valid() {
+        let mut camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        camp.scan_id = Some(1);
+        let mut backed_camp = BackedCampaign::new(camp, BACKER.to_string(), "p2s".to_string(), vec![BackingTx::new("backing".to_string(), NanoErg(1500000000), NanoErg(TX_FEE))]);
+        let node = MockNodeClient {
+            height: 900,
+            p2s_boxes: vec![node_box("a", 1500000000, "1000")],
+            scan_boxes: vec![node_box("a", 1500000000, "1000"), node_box("b", 1000000000, "1000"), node_box("c", 7, "0008cd02aa")],
+            ..MockNodeClient::default()
+        };
+        let status = backed_camp.get_status(&node).unwrap();
+        assert_eq!(status.raised, NanoErg(2500000000));
+        assert_eq!(status.goal, NanoErg(2000000000));
+        assert_eq!(status.blocks_remaining, 100);
+        assert_eq!(status.state, CampaignState::Succeeded);
+        assert_eq!(status.backing_txs, vec![("backing".to_string(), TxStatus::Confirmed(898))]);
+        backed_camp.campaign.scan_id = None;
+        assert!(backed_camp.get_status(&node).is_err());
+    }
+
+    #[test]
+    fn backing_summary_is_valid() {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        let node = MockNodeClient { height: 900, ..MockNodeClient::default() };
+        let summary = camp.backing_summary(&node, BACKER, NanoErg(1000000000), TX_FEE).unwrap();
+        assert_eq!(summary.p2s_address, "p2s");
+        assert_eq!(summary.balance_after(), NanoErg(1999000000));
+        assert_eq!(camp.backing_summary(&node, BACKER, NanoErg(1000000000), 2 * TX_FEE).unwrap().balance_after(), NanoErg(1998000000));
+        assert!(camp.backing_summary(&node, BACKER, NanoErg(0), TX_FEE).is_err());
+        assert!(camp.backing_summary(&node, BACKER, NanoErg(3000000000), TX_FEE).is_err());
+
+        let legacy : BackingTx = serde_json::from_str(r#"{"tx_id": "tx", "backed_amount": 1.5}"#).unwrap();
+        assert_eq!(legacy.fee, NanoErg(TX_FEE));
+    }
+
+    #[test]
+    fn backing_again_is_valid() {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        let node = MockNodeClient { height: 900, ..MockNodeClient::default() };
+        let backed_camp = camp.back_campaign(&node, &camp.backing_summary(&node, BACKER, NanoErg(1000000000), TX_FEE).unwrap()).unwrap();
+        let backed_camp = backed_camp.back_campaign(&node, &camp.backing_summary(&node, BACKER, NanoErg(500000000), TX_FEE).unwrap()).unwrap();
+        assert_eq!(backed_camp.backer_txs.len(), 2);
+        assert!(backed_camp.back_campaign(&node, &camp.backing_summary(&node, CREATOR, NanoErg(500000000), TX_FEE).unwrap()).is_err());
+        assert_eq!(backed_camp.backer_address, BACKER);
+    }
+
+    #[test]
+    fn refund_is_valid() {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        let backing_txs = vec![BackingTx::new("backing".to_string(), NanoErg(1500000000), NanoErg(TX_FEE)), BackingTx::new("other".to_string(), NanoErg(1000000), NanoErg(TX_FEE))];
+        let backed_camp = BackedCampaign::new(camp, BACKER.to_string(), "p2s".to_string(), backing_txs);
+
+        let node = MockNodeClient { height: 999, p2s_boxes: vec![node_box("a", 1500000000, "1000")], ..MockNodeClient::default() };
+        assert!(backed_camp.refund_campaign(&node, TX_FEE).is_err());
+        let node = MockNodeClient { height: 1000, ..MockNodeClient::default() };
+        assert!(backed_camp.refund_campaign(&node, TX_FEE).is_err());
+        let node = MockNodeClient { height: 1000, p2s_boxes: vec![node_box("a", TX_FEE, "1000")], ..MockNodeClient::default() };
+        assert!(backed_camp.refund_campaign(&node, TX_FEE).is_err());
+        assert!(node.sent.borrow().is_empty());
+
+        let node = MockNodeClient { height: 1000, p2s_boxes: vec![node_box("a", 1500000000, "1000")], ..MockNodeClient::default() };
+        let refunded = backed_camp.refund_campaign(&node, TX_FEE).unwrap();
+        assert_eq!(*node.sent.borrow(), vec![(BACKER.to_string(), 1500000000 - TX_FEE, TX_FEE, vec!["a".to_string()])]);
+        assert_eq!(refunded.backer_txs[0].refund_tx_id.as_deref(), Some("tx"));
+        assert_eq!(refunded.backer_txs[1].refund_tx_id, None);
+    }
+
+    #[test]
+    fn collect_is_valid() {
+        let mut camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        let scan_boxes = vec![node_box("a", 1500000000, "1000"), node_box("b", 1000000000, "1000"), node_box("c", 7, "0008cd02aa")];
+        let node = MockNodeClient { height: 900, scan_boxes: scan_boxes.clone(), wallet: vec![CREATOR.to_string()], ..MockNodeClient::default() };
+        assert!(camp.collect(&node, TX_FEE).is_err());
+        camp.scan_id = Some(1);
+        assert!(camp.collect(&MockNodeClient { height: 900, scan_boxes: scan_boxes.clone(), wallet: vec![BACKER.to_string()], ..MockNodeClient::default() }, TX_FEE).is_err());
+        assert!(camp.collect(&MockNodeClient { height: 1000, scan_boxes: scan_boxes.clone(), wallet: vec![CREATOR.to_string()], ..MockNodeClient::default() }, TX_FEE).is_err());
+        assert!(camp.collect(&MockNodeClient { height: 900, scan_boxes: vec![node_box("a", 2000000000, "1000")], wallet: vec![CREATOR.to_string()], ..MockNodeClient::default() }, TX_FEE).is_err());
+        assert!(node.sent.borrow().is_empty());
+
+        let collected = camp.collect(&node, TX_FEE).unwrap();
+        assert_eq!(*node.sent.borrow(), vec![(CREATOR.to_string(), 2500000000 - TX_FEE, TX_FEE, vec!["a".to_string(), "b".to_string()])]);
+        assert_eq!(collected.collect_tx_id.as_deref(), Some("tx"));
+    }
+
+    #[test]
+    fn tx_status_is_valid() {
+        assert_eq!(describe_tx_status(&TxStatus::Confirmed(898), 900), "3 confirmations (included at block 898)");
+        let legacy : BackingTx = serde_json::from_str(r#"{"tx_id": "tx", "backed_amount": 1.5}"#).unwrap();
+        assert_eq!(legacy.status, TxStatus::Pending);
+        let mut tx = BackingTx::new("tx".to_string(), NanoErg(1), NanoErg(TX_FEE));
+        tx.status = TxStatus::Confirmed(898);
+        let saved = serde_json::to_string(&tx).unwrap();
+        assert_eq!(serde_json::from_str::<BackingTx>(&saved).unwrap().status, TxStatus::Confirmed(898));
+    }
+
+    #[test]
+    fn campaign_script_matching_is_valid() {
+        let pk_a = "02".to_string() + &"a".repeat(64);
+        let pk_b = "03".to_string() + &"b".repeat(64);
+        let reference = format!("100204a00b08cd{}08cd{}d1ed", pk_a, pk_a);
+        assert!(matches_campaign_script(&reference, &reference));
+        assert!(matches_campaign_script(&reference, &format!("100204a00b08cd{}08cd{}d1ed", pk_b, pk_a)));
+        assert!(!matches_campaign_script(&reference, &format!("100204a00b08cd{}08cd{}d1ed", pk_b, pk_b)));
+        assert!(!matches_campaign_script(&reference, &format!("100204a00c08cd{}08cd{}d1ed", pk_b, pk_a)));
+        assert!(!matches_campaign_script(&reference, &format!("0008cd{}", pk_a)));
+    }
+
+    #[test]
+    fn campaign_goal_is_valid() {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "12.5 ERG", Network::Mainnet).unwrap();
+        assert_eq!(camp.goal, NanoErg(12500000000));
+        assert!(camp.build_script(BACKER).unwrap().contains("val minToRaise = 12500000000L \\n"));
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        assert!(camp.build_script(BACKER).unwrap().contains("val minToRaise = 2L * 1000000000 \\n"));
+
+        let legacy = format!(r#"{{"name": "Test", "address": "{}", "deadline": 1000, "goal": 2}}"#, CREATOR);
+        let camp : Campaign = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(camp.goal, NanoErg(2000000000));
+        let saved = serde_json::to_string(&camp).unwrap();
+        assert!(saved.contains(r#""goal_nanoerg":2000000000"#));
+        assert_eq!(serde_json::from_str::<Campaign>(&saved).unwrap().goal, NanoErg(2000000000));
+    }
+
+    #[test]
+    fn campaign_chain_state_is_valid() {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        assert!(camp.check_chain_state(900, false).unwrap().is_empty());
+        assert!(camp.check_chain_state(1000, false).is_err());
+        assert!(camp.check_chain_state(980, false).is_err());
+        assert_eq!(camp.check_chain_state(1200, true).unwrap().len(), 1);
+        assert!(camp.deadline_eta(999).unwrap() > Utc::now());
+        assert!(camp.deadline_info(900).contains("(in 100 blocks)"));
+        assert!(camp.deadline_eta(1000).is_none());
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "1000 nanoerg", Network::Mainnet).unwrap();
+        assert_eq!(camp.check_chain_state(900, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn find_campaign_is_valid() {
+        let camps = || -> Vec<Box<dyn CrowdfundingCampaign>> {
+            ["2", "Test"].iter().map(|name| Box::new(Campaign::new(name, CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap()) as Box<dyn CrowdfundingCampaign>).collect()
+        };
+        assert_eq!(find_campaign(camps(), "2").unwrap().get_campaign().name, "Test");
+        assert_eq!(find_campaign(camps(), "1").unwrap().get_campaign().name, "2");
+        assert_eq!(find_campaign(camps(), "Test").unwrap().get_campaign().name, "Test");
+        assert!(find_campaign(camps(), "3").is_err());
+    }
+
+    #[test]
+    fn campaign_state_is_valid() {
+        assert_eq!(CampaignState::derive(100, 200, 5, 10, false), CampaignState::Active);
+        assert_eq!(CampaignState::derive(100, 200, 10, 10, false), CampaignState::Succeeded);
+        assert_eq!(CampaignState::derive(250, 200, 0, 10, true), CampaignState::Succeeded);
+        assert_eq!(CampaignState::derive(200, 200, 5, 10, false), CampaignState::Failed);
+        assert_eq!(CampaignState::derive(200, 200, 10, 10, false), CampaignState::ExpiredUnclaimed);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::address::Network;
+use crate::amount::NanoErg;
+use crate::api_key::{encrypt_api_key, env_passphrase};
+use crate::error::{ErgoCfError, Result};
+use crate::storage::StorageBackend;
+use crate::wallet_reqs::TX_FEE;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs::{File, read_to_string, remove_file};
+use std::io::prelude::*;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+
+/// Name of the config file, see `Config::path` for the folder it is read from
+pub static CONFIG_FILE : &str = "ergo_cf.toml";
+/// File which, when placed next to the binary, keeps the config & data dir next to the binary as well
+pub static PORTABLE_MARKER : &str = "ergo_cf.portable";
+/// Name of the folder created in the XDG config & data folders
+static APP_FOLDER : &str = "ergo_cf";
+/// Legacy file which held the node ip/port before the config file existed
+static LEGACY_NODE_IP_FILE : &str = "node.ip";
+/// Legacy file which held the plaintext api key before the config file existed
+static LEGACY_API_KEY_FILE : &str = "api.key";
+/// Folder in the working directory which held local storage before the data dir existed
+static LEGACY_STORAGE_FOLDER : &str = ".storage";
+
+/// Every key which can be read or written with `ergo_cf config get/set`
+pub static CONFIG_KEYS : [&str; 9] = ["node_url", "api_key", "api_key_env", "api_key_command", "network", "data_dir", "storage", "fee", "timeout"];
+
+/// Keys set per profile by `config set` (besides `api_key`), the others are shared by all profiles
+static PROFILE_KEYS : [&str; 4] = ["node_url", "network", "api_key_env", "api_key_command"];
+
+/// Name which selects the top level node settings instead of a named profile
+pub static DEFAULT_PROFILE : &str = "default";
+
+/// Datatype which holds a named node profile, which replaces the top level node settings when selected.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Profile {
+    /// Url (ip & port) of the node api
+    pub node_url: String,
+    /// Either `mainnet` or `testnet`
+    pub network: Network,
+    /// Node api key encrypted with the user's passphrase via `ergo_cf apikey set --profile <name>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_encrypted: Option<String>,
+    /// External command (e.g. a password manager) which prints the node api key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+    /// Environment variable to read the node api key from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+}
+
+/// Datatype which holds the configuration of the CLI tool.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Url (ip & port) of the node api
+    pub node_url: String,
+    /// Node api key in plaintext, only kept for configs migrated from `api.key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Node api key encrypted with the user's passphrase via `ergo_cf apikey set`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_encrypted: Option<String>,
+    /// External command (e.g. a password manager) which prints the node api key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+    /// Environment variable to read the node api key from, taking priority over `api_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// Either `mainnet` or `testnet`
+    pub network: Network,
+    /// Folder which holds local storage and exported campaigns, `default_data_dir()` if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+    /// Where the tracked campaigns are stored, `sqlite` or `json`
+    pub storage: StorageBackend,
+    /// Fee paid for transactions built by the CLI tool. Set & shown in Erg, stored as an integer of nanoErg.
+    pub fee: NanoErg,
+    /// Timeout in seconds for requests to the node
+    pub timeout: u64,
+    /// Profile used when `--profile` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Named node profiles
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Profile selected for this run (never saved)
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Whether the config file holds the api key in plaintext, e.g. migrated from the legacy `api.key` file (never saved)
+    #[serde(skip)]
+    pub plaintext_api_key_saved: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            node_url: "http://0.0.0.0:9052".to_string(),
+            api_key: None,
+            api_key_encrypted: None,
+            api_key_command: None,
+            api_key_env: None,
+            network: Network::Mainnet,
+            data_dir: None,
+            storage: StorageBackend::Sqlite,
+            fee: NanoErg(TX_FEE),
+            timeout: 30,
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            profile: None,
+            plaintext_api_key_saved: false,
+        }
+    }
+}
+
+impl Config {
+    /// Path of the config file: `ERGO_CF_CONFIG`, else next to the binary in portable mode, else in the current working directory if one was created there by earlier versions, else in the XDG config folder
+    pub fn path() -> String {
+        if let Some(path) = std::env::var("ERGO_CF_CONFIG").ok().filter(|path| !path.is_empty()) {
+            return path;
+        }
+        if let Some(dir) = portable_dir() {
+            return dir.join(CONFIG_FILE).to_string_lossy().to_string();
+        }
+        if Path::new(CONFIG_FILE).exists() {
+            return CONFIG_FILE.to_string();
+        }
+        match xdg_dir("XDG_CONFIG_HOME", ".config") {
+            Some(dir) => dir.join(CONFIG_FILE).to_string_lossy().to_string(),
+            None => CONFIG_FILE.to_string(),
+        }
+    }
+
+    /// Folder which holds local storage and exported campaigns
+    pub fn data_dir(&self) -> String {
+        self.data_dir.clone().unwrap_or_else(default_data_dir)
+    }
+
+    /// Reads the config file exactly as stored. If it does not exist yet it is created, migrating the legacy `node.ip` & `api.key` files.
+    pub fn from_file() -> Result<Config> {
+        let path = Config::path();
+        let mut config = if !Path::new(&path).exists() {
+            let config = Config::migrate_legacy_files()?;
+            config.save()?;
+            remove_file(LEGACY_NODE_IP_FILE).ok();
+            remove_file(LEGACY_API_KEY_FILE).ok();
+            config
+        }
+        else {
+            let text = read_to_string(&path)?;
+            toml::from_str::<Config>(&text).map_err(|e| ErgoCfError::Storage(format!("Failed to read `{}`: {}", path, e)))?
+        };
+        config.plaintext_api_key_saved = config.api_key.is_some();
+        Ok(config)
+    }
+
+    /// Reads the config file, selects the node profile (`profile`, else `ERGO_CF_PROFILE`, else the active profile) and applies the `ERGO_CF_*` environment variable overrides on top
+    pub fn load(profile: Option<&str>) -> Result<Config> {
+        let mut config = Config::from_file()?;
+        let active_profile = config.active_profile.clone().filter(|name| config.profiles.contains_key(name));
+        let profile = profile.map(|p| p.to_string())
+            .or_else(|| std::env::var("ERGO_CF_PROFILE").ok())
+            .or(active_profile);
+        if let Some(name) = profile {
+            config.apply_profile(&name)?;
+        }
+        for key in CONFIG_KEYS.iter() {
+            if let Ok(value) = std::env::var(format!("ERGO_CF_{}", key.to_uppercase())) {
+                config.set(key, &value)?;
+                if *key == "api_key" {
+                    config.api_key_encrypted = None;
+                    config.api_key_command = None;
+                    config.api_key_env = None;
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Replaces the top level node settings with the ones of the named profile
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE {
+            return Ok(());
+        }
+        let profile = self.get_profile(name)?.clone();
+        self.node_url = profile.node_url;
+        self.network = profile.network;
+        self.api_key = None;
+        self.api_key_encrypted = profile.api_key_encrypted;
+        self.api_key_command = profile.api_key_command;
+        self.api_key_env = profile.api_key_env;
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Gets the named profile
+    pub fn get_profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| unknown_profile(name))
+    }
+
+    /// Adds (or replaces) a named profile
+    pub fn add_profile(&mut self, name: &str, node_url: &str, network: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE || name.trim().is_empty() {
+            return Err(ErgoCfError::InvalidConfig(format!("`{}` can not be used as a profile name.", name)));
+        }
+        let profile = Profile {
+            node_url: node_url.trim().trim_end_matches('/').to_string(),
+            network: Network::parse(network)?,
+            api_key_encrypted: None,
+            api_key_command: None,
+            api_key_env: None,
+        };
+        self.profiles.insert(name.to_string(), profile);
+        Ok(())
+    }
+
+    /// Removes a named profile, deactivating it if it was the active one
+    pub fn remove_profile(&mut self, name: &str) -> Result<()> {
+        self.profiles.remove(name).ok_or_else(|| unknown_profile(name))?;
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        Ok(())
+    }
+
+    /// Makes the named profile the one used when `--profile` is not given. `default` goes back to the top level node settings.
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE {
+            self.active_profile = None;
+            return Ok(());
+        }
+        self.get_profile(name)?;
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Sets (or with `None` removes) the encrypted api key of the named profile, or of the top level node settings. Any plaintext api key is removed.
+    pub fn set_api_key_encrypted(&mut self, profile: Option<&str>, encrypted: Option<String>) -> Result<()> {
+        match profile {
+            Some(name) if name != DEFAULT_PROFILE => {
+                self.profiles.get_mut(name).ok_or_else(|| unknown_profile(name))?.api_key_encrypted = encrypted;
+            }
+            _ => {
+                self.api_key = None;
+                self.api_key_encrypted = encrypted;
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the default config with the values of the legacy `node.ip` & `api.key` files (if they exist).
+    /// The api key is encrypted with the `ERGO_CF_PASSPHRASE` passphrase if it is set, else it is kept in plaintext.
+    /// If those files or a legacy `.storage` folder are in the working directory, it is pinned as the data dir so previously tracked campaigns stay in use.
+    fn migrate_legacy_files() -> Result<Config> {
+        let mut config = Config::default();
+        let legacy = [LEGACY_NODE_IP_FILE, LEGACY_API_KEY_FILE, LEGACY_STORAGE_FOLDER].iter().any(|path| Path::new(path).exists());
+        if legacy {
+            config.data_dir = Some(std::env::current_dir()?.to_string_lossy().to_string());
+        }
+        if let Ok(node_ip) = read_to_string(LEGACY_NODE_IP_FILE) {
+            config.node_url = node_ip.trim().to_string();
+        }
+        if let Ok(api_key) = read_to_string(LEGACY_API_KEY_FILE) {
+            match env_passphrase() {
+                Some(passphrase) => config.api_key_encrypted = Some(encrypt_api_key(api_key.trim(), &passphrase)?),
+                None => config.api_key = Some(api_key.trim().to_string()),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Saves the config to the config file
+    pub fn save(&self) -> Result<()> {
+        let text = toml::to_string_pretty(self).map_err(|e| ErgoCfError::Storage(e.to_string()))?;
+        let path = Config::path();
+        if let Some(dir) = Path::new(&path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_dir_all(dir)?;
+        }
+        let mut file = File::create(path)?;
+        file.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Gets the value of a config key as a string. The api key is never returned, only whether one is saved (see `redacted()`).
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match key {
+            "node_url" => self.node_url.clone(),
+            "api_key" => self.redacted_api_key().unwrap_or_default(),
+            "api_key_env" => self.api_key_env.clone().unwrap_or_default(),
+            "api_key_command" => self.api_key_command.clone().unwrap_or_default(),
+            "network" => self.network.to_string(),
+            "data_dir" => self.data_dir(),
+            "storage" => self.storage.to_string(),
+            "fee" => self.fee.to_string(),
+            "timeout" => self.timeout.to_string(),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
+
+    /// Sets a config key from a string value. An empty value unsets optional keys.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let optional = if value.is_empty() { None } else { Some(value.to_string()) };
+        match key {
+            "node_url" => self.node_url = value.trim_end_matches('/').to_string(),
+            "api_key" => self.api_key = optional,
+            "api_key_env" => self.api_key_env = optional,
+            "api_key_command" => self.api_key_command = optional,
+            "network" => self.network = Network::parse(value)?,
+            "data_dir" => self.data_dir = optional,
+            "storage" => self.storage = StorageBackend::parse(value)?,
+            "fee" => self.fee = check_fee(NanoErg::parse_amount(value).map_err(|e| ErgoCfError::InvalidConfig(format!("`fee` must be an amount of Erg: {}", e)))?)?,
+            "timeout" => self.timeout = parse_number(key, value)?,
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    /// Sets a config key like `set()`, but the node settings `node_url`, `network`, `api_key_env` & `api_key_command` go into the named profile.
+    /// The other keys are shared by all profiles and set at the top level.
+    pub fn set_in_profile(&mut self, profile: Option<&str>, key: &str, value: &str) -> Result<()> {
+        let name = match profile {
+            Some(name) if name != DEFAULT_PROFILE && PROFILE_KEYS.contains(&key) => name,
+            _ => return self.set(key, value),
+        };
+        // Parses the value the same way as for the top level node settings
+        let mut parsed = self.clone();
+        parsed.set(key, value)?;
+        let profile = self.profiles.get_mut(name).ok_or_else(|| unknown_profile(name))?;
+        match key {
+            "node_url" => profile.node_url = parsed.node_url,
+            "network" => profile.network = parsed.network,
+            "api_key_env" => profile.api_key_env = parsed.api_key_env,
+            _ => profile.api_key_command = parsed.api_key_command,
+        }
+        Ok(())
+    }
+
+    /// The config with the api keys of the top level node settings and of every profile hidden, for displaying
+    pub fn redacted(&self) -> Config {
+        let mut config = self.clone();
+        config.api_key = self.redacted_api_key();
+        config.api_key_encrypted = None;
+        for profile in config.profiles.values_mut() {
+            if profile.api_key_encrypted.is_some() {
+                profile.api_key_encrypted = Some("(encrypted)".to_string());
+            }
+        }
+        config
+    }
+
+    /// `********` for a plaintext api key, `(encrypted)` for an encrypted one
+    fn redacted_api_key(&self) -> Option<String> {
+        if self.api_key.is_some() {
+            Some("********".to_string())
+        }
+        else if self.api_key_encrypted.is_some() {
+            Some("(encrypted)".to_string())
+        }
+        else {
+            None
+        }
+    }
+
+    /// The config as `key = value` lines
+    pub fn info(&self) -> String {
+        CONFIG_KEYS.iter()
+            .map(|key| format!("{} = {}", key, self.get(key).unwrap_or_default()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Folder of the binary if the portable mode marker file is next to it
+pub fn portable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    if dir.join(PORTABLE_MARKER).exists() { Some(dir.to_path_buf()) } else { None }
+}
+
+/// Data dir used when none is configured: the folder of the binary in portable mode, else the XDG data folder (`~/.local/share/ergo_cf`)
+pub fn default_data_dir() -> String {
+    portable_dir()
+        .or_else(|| xdg_dir("XDG_DATA_HOME", ".local/share"))
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+/// The `ergo_cf` folder in the XDG base directory given by `var`, falling back to `fallback` inside the home folder
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = std::env::var_os(var).filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").filter(|dir| !dir.is_empty()).map(|home| PathBuf::from(home).join(fallback)))?;
+    Some(base.join(APP_FOLDER))
+}
+
+/// Checks that a transaction fee is at least the default fee of 0.001 Erg, which the node requires
+pub fn check_fee(fee: NanoErg) -> Result<NanoErg> {
+    if fee < NanoErg(TX_FEE) {
+        return Err(ErgoCfError::InvalidConfig(format!("The fee of {} Erg is below the minimum fee of {} Erg.", fee, NanoErg(TX_FEE))));
+    }
+    Ok(fee)
+}
+
+fn unknown_profile(name: &str) -> ErgoCfError {
+    ErgoCfError::InvalidConfig(format!("There is no profile named `{}`. See `ergo_cf profile list`.", name))
+}
+
+fn parse_number(key: &str, value: &str) -> Result<u64> {
+    value.parse().map_err(|_| ErgoCfError::InvalidConfig(format!("`{}` must be a whole number, got `{}`.", key, value)))
+}
+
+fn unknown_key(key: &str) -> ErgoCfError {
+    ErgoCfError::InvalidConfig(format!("Unknown config key `{}`. Valid keys are: {}.", key, CONFIG_KEYS.join(", ")))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_get_set_is_valid() {
+        let mut config = Config::default();
+        config.set("node_url", "http://127.0.0.1:9053/").unwrap();
+        config.set("fee", "0.002").unwrap();
+        config.set("api_key", "hello").unwrap();
+        assert_eq!(config.get("node_url").unwrap(), "http://127.0.0.1:9053");
+        assert_eq!(config.fee, NanoErg(2000000));
+        assert_eq!(config.get("fee").unwrap(), "0.002");
+        config.set("fee", "1500000 nanoerg").unwrap();
+        assert_eq!(config.fee, NanoErg(1500000));
+        assert_eq!(config.get("api_key").unwrap(), "********");
+        assert_eq!(config.redacted().api_key.as_deref(), Some("********"));
+        assert!(config.set("network", "devnet").is_err());
+        config.set("storage", "json").unwrap();
+        assert!(config.set("storage", "csv").is_err());
+        assert!(config.set("fee", "0.1 mErg").is_err());
+        assert!(config.set("timeout", "0.1").is_err());
+        assert!(config.get("colour").is_err());
+
+        let text = toml::to_string_pretty(&config).unwrap();
+        let parsed : Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, config);
+        let partial : Config = toml::from_str("node_url = \"http://10.0.0.1:9052\"").unwrap();
+        assert_eq!(partial.fee, Config::default().fee);
+    }
+
+    #[test]
+    fn config_profiles_are_valid() {
+        let mut config = Config { api_key: Some("hello".to_string()), ..Config::default() };
+        config.add_profile("testnet", "http://127.0.0.1:9052/", "testnet").unwrap();
+        assert!(config.add_profile("default", "http://127.0.0.1:9052", "mainnet").is_err());
+        assert!(config.add_profile("other", "http://127.0.0.1:9052", "devnet").is_err());
+        assert!(config.use_profile("other").is_err());
+        config.use_profile("testnet").unwrap();
+        config.set_api_key_encrypted(Some("testnet"), Some("encrypted".to_string())).unwrap();
+
+        let text = toml::to_string_pretty(&config).unwrap();
+        let mut parsed : Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, config);
+        assert_eq!(config.redacted().profiles["testnet"].api_key_encrypted.as_deref(), Some("(encrypted)"));
+        parsed.apply_profile("testnet").unwrap();
+        assert_eq!(parsed.node_url, "http://127.0.0.1:9052");
+        assert_eq!(parsed.network, Network::Testnet);
+        assert_eq!(parsed.api_key, None);
+        assert_eq!(parsed.api_key_encrypted.as_deref(), Some("encrypted"));
+
+        config.remove_profile("testnet").unwrap();
+        assert_eq!(config.active_profile, None);
+        assert!(config.remove_profile("testnet").is_err());
+    }
+}
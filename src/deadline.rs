@@ -0,0 +1,123 @@
+use crate::error::{ErgoCfError, Result};
+use crate::wallet_reqs::NodeClient;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use std::convert::TryFrom;
+
+/// Target time between two Ergo blocks in seconds
+pub const BLOCK_INTERVAL_SECS : i64 = 120;
+
+/// The deadline of a Campaign: the block height, and the date/time it was estimated from (if it was given as one).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deadline {
+    pub height: u64,
+    pub target: Option<DateTime<Utc>>,
+}
+
+impl Deadline {
+    /// A deadline given directly as a block height
+    pub fn at_height(height: u64) -> Deadline {
+        Deadline { height, target: None }
+    }
+
+    /// Parses a deadline given as a block height (`50000`), a date/time (`2026-12-31T12:00Z` or `2026-12-31`) or a duration from now (`+30d`, `+12h`, `+90m`, `+2w`).
+    /// Date/times & durations are converted into a height from the current height of the node and the target block interval.
+    pub fn parse(input: &str, node: &dyn NodeClient) -> Result<Deadline> {
+        let input = input.trim();
+        if let Ok(height) = input.parse::<u64>() {
+            return Ok(Deadline::at_height(height));
+        }
+        let now = Utc::now();
+        let target = match input.strip_prefix('+') {
+            Some(duration) => after_duration(now, duration)?,
+            None => parse_date_time(input)?,
+        };
+        Deadline::from_target(target, now, node.current_height()?)
+    }
+
+    /// Estimates the height which will be reached at `target`
+    fn from_target(target: DateTime<Utc>, now: DateTime<Utc>, current_height: u64) -> Result<Deadline> {
+        let seconds = (target - now).num_seconds();
+        if seconds <= 0 {
+            return Err(ErgoCfError::InvalidCampaign(format!("The deadline {} is in the past.", format_date_time(&target))));
+        }
+        let blocks = (seconds + BLOCK_INTERVAL_SECS - 1) / BLOCK_INTERVAL_SECS;
+        let height = current_height.checked_add(blocks as u64)
+            .ok_or_else(|| ErgoCfError::InvalidCampaign(format!("The deadline {} is too far in the future.", format_date_time(&target))))?;
+        Ok(Deadline { height, target: Some(target) })
+    }
+}
+
+/// The date/time a duration such as `30d` after `now`
+fn after_duration(now: DateTime<Utc>, input: &str) -> Result<DateTime<Utc>> {
+    now.checked_add_signed(parse_duration(input)?)
+        .ok_or_else(|| ErgoCfError::InvalidCampaign(format!("The duration `+{}` is too far in the future.", input)))
+}
+
+/// Parses a duration such as `30d`
+fn parse_duration(input: &str) -> Result<Duration> {
+    let invalid = || ErgoCfError::InvalidCampaign(format!("`+{}` is not a valid duration. Use a number followed by `m`, `h`, `d` or `w`, e.g. `+30d`.", input));
+    let split = input.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = input.split_at(split);
+    let amount : i64 = amount.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => return Err(invalid()),
+    };
+    duration.ok_or_else(|| ErgoCfError::InvalidCampaign(format!("The duration `+{}` is too long.", input)))
+}
+
+/// Parses an RFC 3339 date/time, allowing the seconds (and for a plain date the whole time) to be left out. Times without an offset are UTC.
+fn parse_date_time(input: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    let naive = input.trim_end_matches(['Z', 'z']);
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(naive, format) {
+            return Ok(DateTime::from_naive_utc_and_offset(date_time, Utc));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(naive, "%Y-%m-%d") {
+        return Ok(DateTime::from_naive_utc_and_offset(date.and_hms_opt(0, 0, 0).unwrap_or_default(), Utc));
+    }
+    Err(ErgoCfError::InvalidCampaign(format!("`{}` is not a valid deadline. Use a block height, a date/time such as `2026-12-31T12:00Z` or a duration such as `+30d`.", input)))
+}
+
+/// Estimated time at which the given number of blocks will have been mined, if it is not too far in the future to represent
+pub fn estimate_time(blocks: u64) -> Option<DateTime<Utc>> {
+    let seconds = i64::try_from(blocks).ok()?.checked_mul(BLOCK_INTERVAL_SECS)?;
+    Utc::now().checked_add_signed(Duration::try_seconds(seconds)?)
+}
+
+/// Formats a date/time for displaying
+pub fn format_date_time(date_time: &DateTime<Utc>) -> String {
+    date_time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline_parsing_is_valid() {
+        let now = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let target = parse_date_time("2026-01-02T00:00Z").unwrap();
+        assert_eq!(target, parse_date_time("2026-01-02").unwrap());
+        assert_eq!(target, parse_date_time("2026-01-02T01:00:00+01:00").unwrap());
+        assert_eq!(Deadline::from_target(target, now, 1000).unwrap(), Deadline { height: 1720, target: Some(target) });
+        assert_eq!(Deadline::from_target(after_duration(now, "30d").unwrap(), now, 1000).unwrap().height, 22600);
+        assert_eq!(Deadline::from_target(after_duration(now, "3m").unwrap(), now, 1000).unwrap().height, 1002);
+        assert!(Deadline::from_target(now, now, 1000).is_err());
+        assert!(Deadline::from_target(target, now, u64::MAX).is_err());
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("9999999999999999d").is_err());
+        assert!(after_duration(now, "99999999w").is_err());
+        assert!(estimate_time(u64::MAX).is_none());
+        assert!(parse_date_time("31/12/2026").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, ErgoCfError>;
+
+/// Every error which can occur while using the CLI tool.
+#[derive(Debug)]
+pub enum ErgoCfError {
+    /// The node could not be reached at the configured url
+    NodeUnreachable(String),
+    /// The node rejected the provided api key
+    AuthFailure,
+    /// The node wallet is locked (or has no addresses)
+    WalletLocked,
+    /// The node replied with an error or with data that could not be understood
+    BadNodeResponse(String),
+    /// The provided Campaign data is invalid
+    InvalidCampaign(String),
+    /// The requested action is not possible for the Campaign in its current state
+    Unavailable(String),
+    /// Reading from or writing to local storage failed
+    Storage(String),
+    /// A value required to run the command was not provided (and could not be prompted for)
+    MissingInput(String),
+    /// The passphrase does not unlock the encrypted api key
+    WrongPassphrase,
+    /// A config key, value or profile is invalid
+    InvalidConfig(String),
+}
+
+impl ErgoCfError {
+    /// The process exit code used when exiting with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErgoCfError::InvalidCampaign(_) => 2,
+            ErgoCfError::NodeUnreachable(_) => 3,
+            ErgoCfError::AuthFailure => 4,
+            ErgoCfError::WalletLocked => 5,
+            ErgoCfError::BadNodeResponse(_) => 6,
+            ErgoCfError::Storage(_) => 7,
+            ErgoCfError::Unavailable(_) => 8,
+            ErgoCfError::MissingInput(_) => 9,
+            ErgoCfError::WrongPassphrase => 10,
+            ErgoCfError::InvalidConfig(_) => 11,
+        }
+    }
+}
+
+impl fmt::Display for ErgoCfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErgoCfError::NodeUnreachable(e) => write!(f, "Failed to send request to the node. Please make sure it is running at the `node_url` specified in the config.\n{}", e),
+            ErgoCfError::AuthFailure => write!(f, "The node rejected your api key. Please check your api key with `ergo_cf apikey verify` or save a new one with `ergo_cf apikey set`."),
+            ErgoCfError::WalletLocked => write!(f, "The node wallet is locked or has no addresses. Please unlock your wallet and try again."),
+            ErgoCfError::BadNodeResponse(e) => write!(f, "The node returned an error:\n{}", e),
+            ErgoCfError::InvalidCampaign(e) => write!(f, "{}", e),
+            ErgoCfError::Unavailable(e) => write!(f, "{}", e),
+            ErgoCfError::Storage(e) => write!(f, "Local storage error: {}", e),
+            ErgoCfError::MissingInput(e) => write!(f, "{}", e),
+            ErgoCfError::WrongPassphrase => write!(f, "The passphrase is incorrect, the api key could not be decrypted."),
+            ErgoCfError::InvalidConfig(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ErgoCfError {}
+
+impl From<io::Error> for ErgoCfError {
+    fn from(e: io::Error) -> ErgoCfError {
+        ErgoCfError::Storage(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for ErgoCfError {
+    fn from(e: rusqlite::Error) -> ErgoCfError {
+        ErgoCfError::Storage(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for ErgoCfError {
+    fn from(e: reqwest::Error) -> ErgoCfError {
+        if e.is_serialization() {
+            ErgoCfError::BadNodeResponse(e.to_string())
+        }
+        else {
+            ErgoCfError::NodeUnreachable(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use ergo_cf::address::Network;
+use ergo_cf::api_key::{PASSPHRASE_ENV, env_api_key, env_passphrase, load_api_key, save_api_key};
+use ergo_cf::campaign::{CrowdfundingCampaign, find_campaign};
+use ergo_cf::config::Config;
+use ergo_cf::error::{ErgoCfError, Result};
+use ergo_cf::storage::CampaignStore;
+use ergo_cf::wallet_reqs::{NodeClient, check_wallet_address, network_wallet_addresses};
+
+/// Reads a single trimmed line from stdin. Fails once stdin is closed instead of prompting forever.
+pub fn read_input_line() -> Result<String> {
+    let mut input = String::new();
+    let read = std::io::stdin().read_line(&mut input)?;
+    if read == 0 {
+        return Err(ErgoCfError::MissingInput("No input was provided on stdin.".to_string()));
+    }
+    Ok(input.trim().to_string())
+}
+
+/// Asks the user a yes/no question. `--yes` answers it up front, and in non-interactive mode it must have been.
+pub fn confirm(question: &str, yes: bool, interactive: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput(format!("{} Pass `--yes` to confirm in non-interactive mode.", question)));
+    }
+    println!("{} [y/N]", question);
+    let answer = read_input_line()?.to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// Asks the user for a secret without echoing what they type
+pub fn read_hidden_input(prompt: &str) -> Result<String> {
+    let input = rpassword::prompt_password(prompt)
+        .map_err(|e| ErgoCfError::MissingInput(format!("Failed to read hidden input: {}", e)))?;
+    Ok(input.trim().to_string())
+}
+
+/// Choose a campaign from those which are locally saved. A `selection` (campaign name or list number) skips the prompt.
+pub fn choose_local_campaign(store: &dyn CampaignStore, action_string: &str, selection: Option<&str>, interactive: bool) -> Result<Box<dyn CrowdfundingCampaign>> {
+    let camps = store.list()?;
+    if camps.is_empty() {
+        return Err(ErgoCfError::Unavailable("You have no local Campaigns. Please create or track a Campaign first to interact with one.".to_string()));
+    }
+    if let Some(selection) = selection {
+        return find_campaign(camps, selection);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput(format!("Please choose the campaign to {} with `--campaign <name|id>`.", action_string)));
+    }
+    for (i, camp) in camps.iter().enumerate() {
+        println!("{}", camp.choice_text(i + 1));
+    }
+
+    // Making the campaign selection text more context dependent.
+    println!("\nWhich campaign would you like to {}?", action_string);
+
+
+    // Get Campaign based on choice, the same way as `--campaign`
+    let input = read_input_line()?;
+    match find_campaign(camps, &input) {
+        Ok(camp) => Ok(camp),
+        Err(_) => {
+            println!("Please select a campaign by its number within the range or its name.");
+            choose_local_campaign(store, action_string, None, interactive)
+        }
+    }
+}
+
+/// Gets list of addresses and asks the user to select one, unless an `address` from the wallet was already provided
+pub fn select_wallet_address(node: &dyn NodeClient, address: Option<&str>, network: Network, interactive: bool) -> Result<String> {
+    let address_list = network_wallet_addresses(node, network)?;
+    if let Some(address) = address {
+        check_wallet_address(address, &address_list, network)?;
+        return Ok(address.to_string());
+    }
+    if address_list.len() == 1 {
+        return Ok(address_list[0].clone());
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput("Your wallet has multiple addresses. Please choose one with `--address <addr>`.".to_string()));
+    }
+
+    for (i, address) in address_list.iter().enumerate() {
+        println!("{}. {}", i + 1, address);
+    }
+    println!("Which address would you like to select?");
+    let input = read_input_line()?;
+    if let Ok(input_n) = input.parse::<usize>(){
+        if input_n > address_list.len() || input_n < 1 {
+            println!("Please select an address within the range.");
+            return select_wallet_address(node, None, network, interactive);
+        }
+        return Ok(address_list[input_n-1].clone());
+    }
+    select_wallet_address(node, None, network, interactive)
+}
+
+/// Gets the passphrase from the `ERGO_CF_PASSPHRASE` environment variable, else asks the user for it
+fn get_passphrase(interactive: bool) -> Result<String> {
+    if let Some(passphrase) = env_passphrase() {
+        return Ok(passphrase);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput(format!("Your api key is encrypted. Please provide the passphrase via the `{}` environment variable.", PASSPHRASE_ENV)));
+    }
+    read_hidden_input("Enter the passphrase for your api key: ")
+}
+
+/// Gets a new passphrase from the `ERGO_CF_PASSPHRASE` environment variable, else asks the user for it twice
+pub fn query_new_passphrase(interactive: bool) -> Result<String> {
+    if let Some(passphrase) = env_passphrase() {
+        return Ok(passphrase);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput(format!("Please provide the passphrase to encrypt your api key with via the `{}` environment variable.", PASSPHRASE_ENV)));
+    }
+    let passphrase = read_hidden_input("Choose a passphrase to encrypt your api key with: ")?;
+    if passphrase.is_empty() {
+        println!("The passphrase can not be empty.");
+        return query_new_passphrase(interactive);
+    }
+    if read_hidden_input("Repeat the passphrase: ")? != passphrase {
+        println!("The passphrases do not match.");
+        return query_new_passphrase(interactive);
+    }
+    Ok(passphrase)
+}
+
+/// Asks the user to type in their api key without echoing it
+fn query_api_key() -> Result<String> {
+    let mut input = read_hidden_input("Api key: ")?;
+    input.retain(|c| c != ' ');
+    Ok(input)
+}
+
+/// Gets the saved api key (see `load_api_key()`), asking for the passphrase if it is encrypted.
+/// Else asks the user to enter their api key and saves it encrypted to the config file.
+pub fn check_for_api_key(api_key_env: Option<&str>, config: &Config, interactive: bool) -> Result<String> {
+    if let Some(api_key) = load_api_key(api_key_env, config, || get_passphrase(interactive))? {
+        return Ok(api_key);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput("You do not have your node api key saved. Please provide it with `--api-key-env <var>` or `ergo_cf apikey set`.".to_string()));
+    }
+    println!("You do not have your node api key saved for use with this CLI app.\nPlease enter it now:");
+    let api_key = query_api_key()?;
+    save_api_key(&api_key, &query_new_passphrase(interactive)?, config.profile.as_deref())?;
+    Ok(api_key)
+}
+
+/// Saves a new api key encrypted into the config file for the given profile. The key is read from the `api_key_env` environment variable if given, else the user is asked for it.
+pub fn set_api_key(api_key_env: Option<&str>, profile: Option<&str>, interactive: bool) -> Result<()> {
+    let api_key = match api_key_env {
+        Some(var) => env_api_key(var)?,
+        None if interactive => query_api_key()?,
+        None => return Err(ErgoCfError::MissingInput("Please provide the api key to save via `--api-key-env <var>`.".to_string())),
+    };
+    save_api_key(&api_key, &query_new_passphrase(interactive)?, profile)
+}
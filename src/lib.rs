@@ -0,0 +1,14 @@
+//! Crowdfunding Campaigns on Ergo: building and tracking campaigns, their scripts, local storage and the node client.
+//! Nothing in here reads from or prints to the terminal, the `ergo_cf` CLI tool is built on top of it.
+#[macro_use]
+extern crate serde_json;
+
+pub mod address;
+pub mod amount;
+pub mod api_key;
+pub mod campaign;
+pub mod config;
+pub mod deadline;
+pub mod error;
+pub mod storage;
+pub mod wallet_reqs;
@@ -1,168 +1,504 @@
-extern crate handlebars;
-#[macro_use]
-extern crate serde_json;
+mod input;
+mod output;
 
-mod api_key;
-mod campaign;
-mod wallet_reqs;
-
-use api_key::{check_for_api_key};
-use campaign::{EXPORT_FOLDER, CAMPAIGNS_FOLDER, CrowdfundingCampaign, Campaign, choose_local_campaign};
-use crossterm::{terminal,ClearType};
 use docopt::Docopt;
+use ergo_cf::amount::NanoErg;
+use ergo_cf::api_key::{clear_api_key, save_api_key};
+use ergo_cf::campaign::{CrowdfundingCampaign, Campaign, build_folder_structure, check_backing_amount, export_folder, move_data_dir};
+use ergo_cf::config::{Config, DEFAULT_PROFILE, check_fee, portable_dir};
+use ergo_cf::deadline::Deadline;
+use ergo_cf::error::{ErgoCfError, Result};
+use ergo_cf::storage::{CampaignStore, open_store};
+use ergo_cf::wallet_reqs::{NodeClient, ReqwestNodeClient};
+use input::{check_for_api_key, choose_local_campaign, confirm, query_new_passphrase, read_input_line, select_wallet_address, set_api_key};
+use output::Output;
 use serde::{Deserialize};
-use std::fs::{File, create_dir};
-use std::io::prelude::*;
-use std::path::Path;
-use wallet_reqs::{select_wallet_address};
 
-const USAGE: &'static str = "
+const USAGE: &str = "
 Usage: 
-        ergo_cf back
-        ergo_cf create <campaign-name> <campaign-deadline> <campaign-goal>
-        ergo_cf delete
-        ergo_cf info
-        ergo_cf import <file-path>
-        ergo_cf export
-        ergo_cf track <campaign-name> <campaign-address> <campaign-deadline> <campaign-goal> 
+        ergo_cf apikey (set|clear|verify) [options]
+        ergo_cf back [options]
+        ergo_cf collect [options]
+        ergo_cf config show [options]
+        ergo_cf config get <key> [options]
+        ergo_cf config set <key> <value> [options]
+        ergo_cf create <campaign-name> <campaign-deadline> <campaign-goal> [options]
+        ergo_cf create <campaign-name> <campaign-goal> --deadline <when> [options]
+        ergo_cf data show [options]
+        ergo_cf data move <dir> [options]
+        ergo_cf delete [options]
+        ergo_cf info [options]
+        ergo_cf import <file-path> [options]
+        ergo_cf export [options]
+        ergo_cf profile list [options]
+        ergo_cf profile add <name> <node-url> [options]
+        ergo_cf profile remove <name> [options]
+        ergo_cf profile use <name> [options]
+        ergo_cf refund [options]
+        ergo_cf status [options]
+        ergo_cf track <campaign-name> <campaign-address> <campaign-deadline> <campaign-goal> [options]
+        ergo_cf track <campaign-name> <campaign-address> <campaign-goal> --deadline <when> [options]
+        ergo_cf txs [options]
+
+Options:
+        --deadline <when>       Campaign deadline as a block height, a date/time (`2026-12-31T12:00Z`) or a duration from now (`+30d`).
+        --campaign <name|id>    Select the tracked campaign by list number or else name instead of being asked.
+        --address <addr>        Use this wallet address instead of being asked.
+        --amount <erg>          Back the campaign with this many Erg (up to 9 decimal places) instead of being asked.
+        --fee <erg>             Pay this fee (in Erg, like the `fee` in the config) for transactions sent by this command instead of the configured one.
+        --data-dir <dir>        Keep local storage and exported campaigns in this folder instead of the configured one.
+        --api-key-env <var>     Read the node api key from the given environment variable.
+        --profile <name>        Use the named node profile instead of the active one.
+        --network <network>     Network of the node when adding a profile, `mainnet` or `testnet` [default: mainnet].
+        --force                 Create, track or back a campaign even though its deadline has passed or is very close.
+        --record <file>         Save every request to the node and its response into a fixture file (the api key is redacted).
+        --replay <file>         Answer requests to the node from a fixture file saved via `--record` instead of contacting the node.
+        --yes                   Answer yes to every confirmation.
+        --non-interactive       Never prompt, fail instead whenever a required value is missing.
+        --output <format>       Print results as `text` or as a `json` document (implies --non-interactive) [default: text].
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
+    cmd_apikey: bool,
+    cmd_clear: bool,
+    cmd_verify: bool,
     cmd_back: bool,
+    cmd_collect: bool,
+    cmd_config: bool,
+    cmd_show: bool,
+    cmd_get: bool,
+    cmd_set: bool,
     cmd_create: bool,
+    cmd_data: bool,
+    cmd_move: bool,
     cmd_delete: bool,
     cmd_track: bool,
     cmd_info: bool,
     cmd_import: bool,
     cmd_export: bool,
+    cmd_profile: bool,
+    cmd_list: bool,
+    cmd_add: bool,
+    cmd_remove: bool,
+    cmd_use: bool,
+    cmd_refund: bool,
+    cmd_status: bool,
+    cmd_txs: bool,
     arg_campaign_name: String,
     arg_campaign_address: String,
     arg_campaign_deadline: String,
     arg_campaign_goal: String,
+    arg_dir: String,
     arg_file_path: String,
+    arg_key: String,
+    arg_name: String,
+    arg_node_url: String,
+    arg_value: String,
+    flag_deadline: Option<String>,
+    flag_campaign: Option<String>,
+    flag_address: Option<String>,
+    flag_amount: Option<String>,
+    flag_fee: Option<String>,
+    flag_data_dir: Option<String>,
+    flag_api_key_env: Option<String>,
+    flag_profile: Option<String>,
+    flag_network: String,
+    flag_force: bool,
+    flag_record: Option<String>,
+    flag_replay: Option<String>,
+    flag_yes: bool,
+    flag_non_interactive: bool,
+    flag_output: String,
 }
 
-/// Builds the folder structure for local storage
-fn build_folder_structure() {
-    create_dir(Path::new(STORAGE_FOLDER!())).ok();
-    create_dir(Path::new(CAMPAIGNS_FOLDER)).ok();
-    create_dir(Path::new(EXPORT_FOLDER)).ok();
+/// Track Campagin
+fn track_campaign(store: &dyn CampaignStore, camp: &Campaign, out: &mut Output) -> Result<()> {
+    store.put(camp)?;
+    out.clear_and_title();
+    out.message("Valid Campaign information submitted. This campaign is now being tracked:\n");
+    out.campaign(camp);
+    Ok(())
 }
 
-/// Checks if `node.ip` file exists, else creates default one for node at `http://0.0.0.0:9052`
-fn generate_default_node_ip_file() {
-    let file_path = Path::new("node.ip");
-    if file_path.exists() == false {
-        let node_ip = "http://0.0.0.0:9052".to_string();
-        let mut file = File::create(file_path).expect("Failed to write to node.ip file.");
-        file.write_all(&node_ip.into_bytes()).expect("Failed to write node ip to file.");
+/// The fee in nanoErg for transactions sent by this command, `--fee` (checked by `check_fee_flag()`) overriding the config
+fn tx_fee(args: &Args, config: &Config) -> Result<u64> {
+    match &args.flag_fee {
+        Some(fee) => Ok(NanoErg::parse_amount(fee)?.0),
+        None => Ok(check_fee(config.fee)?.0),
     }
 }
 
-/// Clear terminal screen and print title
-fn clear_and_title(terminal: &crossterm::Terminal) {
-    terminal.clear(ClearType::All);
-    println!("Ergo Crowdfund CLI Tool\n-----------------------");
+/// Prints the warnings about a Campaign
+fn print_warnings(warnings: &[String], out: &mut Output) {
+    for warning in warnings {
+        out.message(&format!("\n{}", warning));
+    }
 }
 
-/// Track Campagin
-fn track_campaign(camp: &Campaign, terminal: &crossterm::Terminal) {
-    camp.clone().save_locally();
-    clear_and_title(&terminal);
-    println!("Valid Campaign information submitted. This campaign is now being tracked:\n");
-    camp.print_info();
+/// Parses the campaign deadline, given either as `<campaign-deadline>` or via `--deadline`
+fn campaign_deadline(args: &Args, node: &dyn NodeClient) -> Result<Deadline> {
+    match &args.flag_deadline {
+        Some(_) if !args.arg_campaign_deadline.is_empty() => Err(ErgoCfError::InvalidCampaign("Please provide the deadline either as `<campaign-deadline>` or via `--deadline`, not both.".to_string())),
+        Some(when) => Deadline::parse(when, node),
+        None => Deadline::parse(&args.arg_campaign_deadline, node),
+    }
+}
+
+/// Creates the client for the configured node, or for the fixture given via `--replay` in which case no api key is needed
+fn connect_node(args: &Args, config: &Config, interactive: bool) -> Result<ReqwestNodeClient> {
+    if let Some(path) = &args.flag_replay {
+        return ReqwestNodeClient::replay_from(path);
+    }
+    let api_key = check_for_api_key(args.flag_api_key_env.as_deref(), config, interactive)?;
+    let node = ReqwestNodeClient::new(&config.node_url, &api_key, config.timeout)?;
+    match &args.flag_record {
+        Some(path) => Ok(node.record_to(path)),
+        None => Ok(node),
+    }
+}
 
+/// Name of the command chosen by the user
+fn command_name(args: &Args) -> &'static str {
+    match () {
+        _ if args.cmd_apikey => "apikey",
+        _ if args.cmd_back => "back",
+        _ if args.cmd_collect => "collect",
+        _ if args.cmd_config => "config",
+        _ if args.cmd_create => "create",
+        _ if args.cmd_data => "data",
+        _ if args.cmd_delete => "delete",
+        _ if args.cmd_info => "info",
+        _ if args.cmd_import => "import",
+        _ if args.cmd_export => "export",
+        _ if args.cmd_profile => "profile",
+        _ if args.cmd_refund => "refund",
+        _ if args.cmd_status => "status",
+        _ if args.cmd_txs => "txs",
+        _ => "track",
+    }
 }
 
-/// Asks user for an amount
-fn query_amount() -> f64 {
+/// Asks user for an amount, unless one was already provided via `--amount`
+fn query_amount(amount: Option<&str>, interactive: bool) -> Result<NanoErg> {
+    if let Some(amount) = amount {
+        return NanoErg::parse_amount(amount).and_then(check_backing_amount);
+    }
+    if !interactive {
+        return Err(ErgoCfError::MissingInput("Please provide the amount of Erg to send with `--amount <erg>`.".to_string()));
+    }
     println!("\nHow many Erg do you want to send to this campaign?");
-    let mut input = String::new();
-    if let Ok(_) = std::io::stdin().read_line(&mut input){
-        if let Ok(input_n) = input.trim().parse::<f64>(){
-            if input_n < 0 as f64 {
-                println!("Please input a valid number greater than 0.");
-                return query_amount();
-            }
-            return input_n;
+    let input = read_input_line()?;
+    match NanoErg::parse_amount(&input).and_then(check_backing_amount) {
+        Ok(amount) => Ok(amount),
+        Err(e) => {
+            println!("{}", e);
+            query_amount(None, interactive)
         }
     }
-    return 0 as f64;
 }
 
-pub fn main() {
-    build_folder_structure();
-    generate_default_node_ip_file();
+/// Rejects an `--output` format other than `text` or `json` as a usage error
+fn check_output_format(args: Args) -> std::result::Result<Args, docopt::Error> {
+    match args.flag_output.as_str() {
+        "text" | "json" => Ok(args),
+        format => Err(docopt::Error::Usage(format!("`{}` is not a valid output format, use either `text` or `json`.", format))),
+    }
+}
+
+/// Rejects a `--fee` which is not an amount of Erg or is below the minimum fee as a usage error
+fn check_fee_flag(args: Args) -> std::result::Result<Args, docopt::Error> {
+    if let Some(fee) = &args.flag_fee {
+        NanoErg::parse_amount(fee).and_then(check_fee)
+            .map_err(|e| docopt::Error::Usage(format!("`{}` is not a valid fee: {}", fee, e)))?;
+    }
+    Ok(args)
+}
 
+pub fn main() {
     // Get basic values
-    let terminal = terminal();
-    clear_and_title(&terminal);
     let args: Args = Docopt::new(USAGE)
     .and_then(|d| d.deserialize())
+    .and_then(check_output_format)
+    .and_then(check_fee_flag)
     .unwrap_or_else(|e| e.exit());
-    let api_key = check_for_api_key();
+    let mut out = Output::new(command_name(&args), args.flag_output == "json");
+    out.clear_and_title();
+
+    let result = run(&args, &mut out);
+    let exit_code = out.finish(result);
+    std::process::exit(exit_code);
+}
+
+/// Runs the command chosen by the user
+fn run(args: &Args, out: &mut Output) -> Result<()> {
+    let mut config = Config::load(args.flag_profile.as_deref())?;
+    if let Some(data_dir) = &args.flag_data_dir {
+        config.data_dir = Some(data_dir.clone());
+    }
+    let data_dir = config.data_dir();
+    let interactive = !args.flag_non_interactive && args.flag_output != "json";
+    let replaces_api_key = (args.cmd_apikey && (args.cmd_set || args.cmd_clear)) || (args.cmd_config && args.cmd_set && args.arg_key == "api_key");
+    if config.plaintext_api_key_saved && !replaces_api_key {
+        out.warning(&format!("Warning: Your api key is saved in plaintext in {}. Run `ergo_cf apikey set` to save it encrypted instead.", Config::path()));
+    }
+
+    // Allows you to see where local storage is kept and to move it elsewhere
+    if args.cmd_data {
+        return run_data(args, &config, out);
+    }
+    build_folder_structure(&data_dir)?;
+
+    // Allows you to view and edit the config file
+    if args.cmd_config {
+        return run_config(args, &config, interactive, out);
+    }
+
+    // Allows you to manage the named node profiles
+    if args.cmd_profile {
+        return run_profile(args, &config, out);
+    }
+
+    let fee = tx_fee(args, &config)?;
+    let selection = args.flag_campaign.as_deref();
+
+    // Allows you to save or remove an encrypted api key
+    if args.cmd_apikey && args.cmd_set {
+        set_api_key(args.flag_api_key_env.as_deref(), config.profile.as_deref(), interactive)?;
+        out.message("Your api key has been encrypted and saved into the config.");
+        return Ok(());
+    }
+    if args.cmd_apikey && args.cmd_clear {
+        clear_api_key(config.profile.as_deref())?;
+        out.message("Your api key has been removed from the config.");
+        return Ok(());
+    }
 
-    clear_and_title(&terminal);
+    let node = connect_node(args, &config, interactive)?;
+    let store = open_store(config.storage, &data_dir)?;
+
+    // Checks that the node accepts the api key. A locked wallet still means the key was accepted.
+    if args.cmd_verify {
+        match node.wallet_addresses() {
+            Ok(_) | Err(ErgoCfError::WalletLocked) => out.message(&format!("Your api key was accepted by the node at {}.", config.node_url)),
+            Err(e) => return Err(e),
+        }
+        return Ok(());
+    }
+
+    out.clear_and_title();
 
     // Allows you to create a new Crowdfunding Campaign
     if args.cmd_create {
-        let address = select_wallet_address(&api_key);
-        let camp = Campaign::new(&args.arg_campaign_name, &address, &args.arg_campaign_deadline, &args.arg_campaign_goal);
-        camp.clone().save_locally();
-        camp.clone().export();
-        clear_and_title(&terminal);
-        println!("Your campaign has been created.\nCheck out the 'export' folder to share the campaign file with others.\n");
-        camp.print_info();
+        let address = select_wallet_address(&node, args.flag_address.as_deref(), config.network, interactive)?;
+        let deadline = campaign_deadline(args, &node)?;
+        let mut camp = Campaign::new(&args.arg_campaign_name, &address, deadline, &args.arg_campaign_goal, config.network)?;
+        let warnings = camp.check_chain_state(node.current_height()?, args.flag_force)?;
+        camp.register_scan(&node)?;
+        store.put(&camp)?;
+        camp.export(&export_folder(&data_dir))?;
+        out.clear_and_title();
+        print_warnings(&warnings, out);
+        out.message("Your campaign has been created.\nCheck out the 'export' folder to share the campaign file with others.\n");
+        out.campaign(&camp);
     }
 
     // Allows you to track a Crowdfunding Campaign
     if args.cmd_track {
-        let camp = Campaign::new(&args.arg_campaign_name, &args.arg_campaign_address, &args.arg_campaign_deadline, &args.arg_campaign_goal);
-        track_campaign(&camp, &terminal);
+        let deadline = campaign_deadline(args, &node)?;
+        let camp = Campaign::new(&args.arg_campaign_name, &args.arg_campaign_address, deadline, &args.arg_campaign_goal, config.network)?;
+        let warnings = camp.check_chain_state(node.current_height()?, args.flag_force)?;
+        track_campaign(store.as_ref(), &camp, out)?;
+        print_warnings(&warnings, out);
     }
 
     // Provides info about a tracked Crowdfunding Campaign
     if args.cmd_info {
-        let text = "see more information about".to_string();
-        let camp = choose_local_campaign(&text);
-        clear_and_title(&terminal);
-        camp.print_info();
+        let camp = choose_local_campaign(store.as_ref(), "see more information about", selection, interactive)?;
+        out.clear_and_title();
+        out.campaign(camp.as_ref());
+        // The info is still shown without the deadline ETA when the node can not be reached
+        if let Ok(height) = node.current_height() {
+            out.message(&camp.get_campaign().deadline_info(height));
+        }
+    }
+
+    // Provides the on-chain progress of a tracked Crowdfunding Campaign
+    if args.cmd_status {
+        let camp = choose_local_campaign(store.as_ref(), "see the status of", selection, interactive)?;
+        out.clear_and_title();
+        let status = camp.get_status(&node)?;
+        store.put_status(&camp.get_campaign().name, &status)?;
+        out.campaign(camp.as_ref());
+        out.status(status);
+    }
+
+    // Refreshes the confirmation state of your backing txs for a tracked Crowdfunding Campaign
+    if args.cmd_txs {
+        let camp = choose_local_campaign(store.as_ref(), "refresh the backing txs of", selection, interactive)?;
+        let backed_camp = camp.as_backed()
+            .ok_or_else(|| ErgoCfError::Unavailable("You have not backed this campaign, so there are no backing txs to refresh.".to_string()))?
+            .refresh_txs(&node)?;
+        store.put(&backed_camp)?;
+        out.clear_and_title();
+        out.campaign(&backed_camp);
     }
 
     // Allows you to import a Crowdfunding Campaign from a file
     if args.cmd_import {
-        let camp = Campaign::from_file(&args.arg_file_path);
-        track_campaign(&camp, &terminal);
+        let camp = Campaign::from_file(&args.arg_file_path)?;
+        camp.check_network(config.network)?;
+        track_campaign(store.as_ref(), &camp, out)?;
     }
 
     // Allows you to export a Crowdfunding Campaign to a file
     if args.cmd_export {
-        let text = "export".to_string();
-        let camp = choose_local_campaign(&text);
-        camp.export();
+        let camp = choose_local_campaign(store.as_ref(), "export", selection, interactive)?;
+        camp.export(&export_folder(&data_dir))?;
+        out.message("Campaign exported into the 'export' folder.");
+        out.campaign(camp.get_campaign());
     }
 
 
     // Allows deletion of tracked Campaign
     if args.cmd_delete {
-        let text = "delete".to_string();
-        let camp = choose_local_campaign(&text);
-        camp.delete();
+        let camp = choose_local_campaign(store.as_ref(), "delete", selection, interactive)?;
+        let question = format!("Are you sure you want to stop tracking `{}`?", camp.get_campaign().name);
+        if confirm(&question, args.flag_yes, interactive)? {
+            store.delete(&camp.get_campaign().name)?;
+            out.message("Campaign deleted from local storage.");
+            out.campaign(camp.as_ref());
+        }
     }
 
     // Allows you to back one of the tracked Crowdfunding Campaigns
     if args.cmd_back {
-        let text = "back".to_string();
-        let camp = choose_local_campaign(&text);
-        clear_and_title(&terminal);
-        camp.print_info();
-        let back_amount = query_amount();
-        clear_and_title(&terminal);
-        let backed_camp = camp.back_campaign(&api_key, back_amount);
-        clear_and_title(&terminal);
-        backed_camp.print_info();
+        let camp = choose_local_campaign(store.as_ref(), "back", selection, interactive)?;
+        camp.get_campaign().check_network(config.network)?;
+        let warnings = camp.get_campaign().check_chain_state(node.current_height()?, args.flag_force)?;
+        out.clear_and_title();
+        if interactive {
+            println!("{}", camp.info());
+        }
+        let back_amount = query_amount(args.flag_amount.as_deref(), interactive)?;
+        out.clear_and_title();
+        let backed_from = camp.as_backed().map(|backed_camp| backed_camp.backer_address.as_str());
+        let backer_address = select_wallet_address(&node, args.flag_address.as_deref().or(backed_from), config.network, interactive)?;
+        if let Some(backed_camp) = camp.as_backed() {
+            backed_camp.check_backer_address(&backer_address)?;
+        }
+        let summary = camp.get_campaign().backing_summary(&node, &backer_address, back_amount, fee)?;
+        out.clear_and_title();
+        out.backing_summary(&summary);
+        print_warnings(&warnings, out);
+        if confirm("Send this backing payment?", args.flag_yes, interactive)? {
+            let backed_camp = camp.back_campaign(&node, &summary)?;
+            store.put(&backed_camp)?;
+            out.clear_and_title();
+            out.campaign(&backed_camp);
+        }
+        else {
+            out.message("The backing payment has been cancelled.");
+        }
+    }
+
+    // Allows a backer to get their funds back from a Campaign which failed to reach its goal before the deadline
+    if args.cmd_refund {
+        let camp = choose_local_campaign(store.as_ref(), "refund", selection, interactive)?;
+        out.clear_and_title();
+        let backed_camp = camp.refund_campaign(&node, fee)?;
+        store.put(&backed_camp)?;
+        out.message(&format!("Your funds have been refunded to {}.\n", backed_camp.backer_address));
+        out.campaign(&backed_camp);
+    }
+
+    // Allows the creator to collect the funds of a Crowdfunding Campaign which reached its goal
+    if args.cmd_collect {
+        let camp = choose_local_campaign(store.as_ref(), "collect", selection, interactive)?;
+        out.clear_and_title();
+        let collected_camp = camp.collect_campaign(&node, fee)?;
+        store.put(collected_camp.as_ref())?;
+        out.message(&format!("The campaign funds have been collected into {}.\n", collected_camp.get_campaign().address));
+        out.campaign(collected_camp.as_ref());
     }
+    Ok(())
 }
 
+/// Runs the `config` subcommands. `show` and `get` include environment variable overrides, `set` only edits the config file.
+/// The api key is never shown, and `set api_key` saves it encrypted like `apikey set`.
+fn run_config(args: &Args, config: &Config, interactive: bool, out: &mut Output) -> Result<()> {
+    if args.cmd_show {
+        let profile = config.profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+        out.message(&format!("Config file: {}\nProfile: {}\n", Config::path(), profile));
+        out.config(&config.redacted());
+    }
+    if args.cmd_get {
+        out.message(&config.get(&args.arg_key)?);
+    }
+    if args.cmd_set && args.arg_key == "api_key" {
+        if args.arg_value.trim().is_empty() {
+            clear_api_key(config.profile.as_deref())?;
+            out.message("Your api key has been removed from the config.");
+        }
+        else {
+            save_api_key(args.arg_value.trim(), &query_new_passphrase(interactive)?, config.profile.as_deref())?;
+            out.message("Your api key has been encrypted and saved into the config.");
+        }
+        return Ok(());
+    }
+    if args.cmd_set {
+        let mut stored = Config::from_file()?;
+        stored.set_in_profile(config.profile.as_deref(), &args.arg_key, &args.arg_value)?;
+        stored.save()?;
+        let mut shown = stored.clone();
+        if let Some(name) = config.profile.as_deref() {
+            shown.apply_profile(name)?;
+        }
+        out.message(&format!("`{}` has been set to `{}`.", args.arg_key, shown.get(&args.arg_key)?));
+    }
+    Ok(())
+}
+
+/// Runs the `data` subcommands. `move` moves the local storage & exported campaigns into `<dir>` and saves it as the `data_dir` in the config file.
+fn run_data(args: &Args, config: &Config, out: &mut Output) -> Result<()> {
+    if args.cmd_show {
+        let mode = if portable_dir().is_some() { " (portable mode)" } else { "" };
+        out.message(&format!("Data dir: {}{}\nConfig file: {}", config.data_dir(), mode, Config::path()));
+    }
+    if args.cmd_move {
+        let new_data_dir = std::path::absolute(&args.arg_dir)?.to_string_lossy().to_string();
+        move_data_dir(&config.data_dir(), &new_data_dir)?;
+        let mut stored = Config::from_file()?;
+        stored.set("data_dir", &new_data_dir)?;
+        stored.save()?;
+        out.message(&format!("Local storage has been moved from `{}` to `{}`, which is now the `data_dir` in the config.", config.data_dir(), new_data_dir));
+        if std::env::var_os("ERGO_CF_DATA_DIR").is_some() {
+            out.warning("The `ERGO_CF_DATA_DIR` environment variable is set and overrides the `data_dir` in the config, please point it at the new data dir or unset it.");
+        }
+    }
+    Ok(())
+}
 
+/// Runs the `profile` subcommands
+fn run_profile(args: &Args, config: &Config, out: &mut Output) -> Result<()> {
+    let mut stored = Config::from_file()?;
+    if args.cmd_list {
+        out.profiles(&stored.redacted().profiles, config.profile.as_deref());
+    }
+    if args.cmd_add {
+        stored.add_profile(&args.arg_name, &args.arg_node_url, &args.flag_network)?;
+        stored.save()?;
+        out.message(&format!("Profile `{}` has been added. Save its api key with `ergo_cf apikey set --profile {}`.", args.arg_name, args.arg_name));
+    }
+    if args.cmd_remove {
+        stored.remove_profile(&args.arg_name)?;
+        stored.save()?;
+        out.message(&format!("Profile `{}` has been removed.", args.arg_name));
+    }
+    if args.cmd_use {
+        stored.use_profile(&args.arg_name)?;
+        stored.save()?;
+        out.message(&format!("Profile `{}` is now used by default.", args.arg_name));
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use ergo_cf::campaign::{CrowdfundingCampaign, Campaign, BackedCampaign, BackingSummary, CampaignStatus};
+use ergo_cf::config::{Config, Profile};
+use ergo_cf::error::ErgoCfError;
+use crossterm::ClearType;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Structured result of a command, printed as a single json document with `--output json`.
+#[derive(Debug, Serialize, Default)]
+pub struct Report {
+    pub command: String,
+    pub messages: Vec<String>,
+    pub campaign: Option<Campaign>,
+    pub backed_campaign: Option<BackedCampaign>,
+    pub status: Option<CampaignStatus>,
+    pub backing_summary: Option<BackingSummary>,
+    pub config: Option<Config>,
+    pub profiles: Option<BTreeMap<String, Profile>>,
+    pub active_profile: Option<String>,
+    pub error: Option<ReportError>,
+}
+
+/// Datatype which holds an error reported in the json document.
+#[derive(Debug, Serialize)]
+pub struct ReportError {
+    pub message: String,
+    pub exit_code: i32,
+}
+
+/// Writes the results of a command either as text to the terminal or as a json document to stdout.
+pub struct Output {
+    terminal: crossterm::Terminal,
+    json: bool,
+    report: Report,
+    /// Warnings printed in text mode once the command has finished, so that clearing the screen can not hide them
+    warnings: Vec<String>,
+}
+
+impl Output {
+    /// Create a new `Output` for the given command
+    pub fn new(command: &str, json: bool) -> Output {
+        Output {
+            terminal: crossterm::terminal(),
+            json,
+            report: Report { command: command.to_string(), ..Report::default() },
+            warnings: vec![],
+        }
+    }
+
+    /// Clear terminal screen and print title. The screen is never cleared in json mode.
+    pub fn clear_and_title(&self) {
+        if !self.json {
+            self.terminal.clear(ClearType::All).ok();
+            println!("Ergo Crowdfund CLI Tool\n-----------------------");
+        }
+    }
+
+    /// Prints a message for the user
+    pub fn message(&mut self, text: &str) {
+        if self.json {
+            self.report.messages.push(text.trim().to_string());
+        }
+        else {
+            println!("{}", text);
+        }
+    }
+
+    /// Prints a warning for the user. In text mode it is printed to stderr when the command finishes.
+    pub fn warning(&mut self, text: &str) {
+        if self.json {
+            self.report.messages.push(text.trim().to_string());
+        }
+        else {
+            self.warnings.push(text.to_string());
+        }
+    }
+
+    /// Prints info about the Campaign
+    pub fn campaign(&mut self, camp: &dyn CrowdfundingCampaign) {
+        if self.json {
+            match camp.as_backed() {
+                Some(backed_camp) => self.report.backed_campaign = Some(backed_camp.clone()),
+                None => self.report.campaign = Some(camp.get_campaign().clone()),
+            }
+        }
+        else {
+            println!("{}", camp.info());
+        }
+    }
+
+    /// Prints the on-chain status of the Campaign
+    pub fn status(&mut self, status: CampaignStatus) {
+        if self.json {
+            self.report.status = Some(status);
+        }
+        else {
+            println!("{}", status.info());
+        }
+    }
+
+    /// Prints the summary of a backing payment before it is sent
+    pub fn backing_summary(&mut self, summary: &BackingSummary) {
+        if self.json {
+            self.report.backing_summary = Some(summary.clone());
+        }
+        else {
+            println!("{}", summary.info());
+        }
+    }
+
+    /// Prints the config
+    pub fn config(&mut self, config: &Config) {
+        if self.json {
+            self.report.config = Some(config.clone());
+        }
+        else {
+            println!("{}", config.info());
+        }
+    }
+
+    /// Prints the named node profiles, marking the one selected for this run
+    pub fn profiles(&mut self, profiles: &BTreeMap<String, Profile>, active: Option<&str>) {
+        if self.json {
+            self.report.profiles = Some(profiles.clone());
+            self.report.active_profile = active.map(|a| a.to_string());
+        }
+        else if profiles.is_empty() {
+            println!("No profiles have been added yet. Add one with `ergo_cf profile add <name> <node-url>`.");
+        }
+        else {
+            for (name, profile) in profiles {
+                let marker = if active == Some(name.as_str()) { "*" } else { " " };
+                println!("{} {}: {} ({})", marker, name, profile.node_url, profile.network);
+            }
+        }
+    }
+
+    /// Finishes the command by printing the error (if any) and the json document. Returns the process exit code.
+    pub fn finish(mut self, result: Result<(), ErgoCfError>) -> i32 {
+        let exit_code = match &result {
+            Ok(_) => 0,
+            Err(e) => e.exit_code(),
+        };
+        if self.json {
+            if let Err(e) = result {
+                self.report.error = Some(ReportError { message: e.to_string(), exit_code });
+            }
+            println!("{}", serde_json::to_string_pretty(&self.report).unwrap_or_default());
+        }
+        else {
+            for warning in &self.warnings {
+                eprintln!("{}", warning);
+            }
+            if let Err(e) = result {
+                eprintln!("{}", e);
+            }
+        }
+        exit_code
+    }
+}
@@ -0,0 +1,563 @@
+use crate::address::Network;
+use crate::amount::NanoErg;
+use crate::campaign::{BackedCampaign, BackingTx, CAMPAIGN_EXTENSION, Campaign, CampaignState, CampaignStatus, CrowdfundingCampaign, campaign_file_path, campaigns_folder, database_path};
+use crate::error::{ErgoCfError, Result};
+use crate::wallet_reqs::TxStatus;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Row, params};
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions, read_dir, read_to_string, remove_file};
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Version of the database schema, stored as the `user_version` of the database once existing `.campaign` files have been imported
+const SCHEMA_VERSION : i64 = 1;
+
+/// Extension of the json lines files holding the status history of a campaign in a `JsonDirStore`
+static HISTORY_EXTENSION : &str = ".history";
+
+static SCHEMA : &str = "
+    CREATE TABLE IF NOT EXISTS campaigns (
+        name TEXT PRIMARY KEY,
+        address TEXT NOT NULL,
+        deadline INTEGER NOT NULL,
+        deadline_target TEXT,
+        goal_nanoerg INTEGER NOT NULL,
+        network TEXT NOT NULL,
+        scan_id INTEGER,
+        collect_tx_id TEXT,
+        backer_address TEXT,
+        p2s_address TEXT
+    );
+    CREATE TABLE IF NOT EXISTS backing_txs (
+        campaign_name TEXT NOT NULL REFERENCES campaigns(name) ON DELETE CASCADE,
+        tx_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        backed_amount INTEGER NOT NULL,
+        fee INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        confirmed_height INTEGER,
+        checked_height INTEGER,
+        PRIMARY KEY (campaign_name, tx_id)
+    );
+    CREATE TABLE IF NOT EXISTS refunds (
+        campaign_name TEXT NOT NULL,
+        backing_tx_id TEXT NOT NULL,
+        refund_tx_id TEXT NOT NULL,
+        PRIMARY KEY (campaign_name, backing_tx_id),
+        FOREIGN KEY (campaign_name, backing_tx_id) REFERENCES backing_txs(campaign_name, tx_id) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS status_snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        campaign_name TEXT NOT NULL REFERENCES campaigns(name) ON DELETE CASCADE,
+        taken_at TEXT NOT NULL,
+        height INTEGER NOT NULL,
+        raised INTEGER NOT NULL,
+        goal INTEGER NOT NULL,
+        state TEXT NOT NULL
+    );";
+
+/// Where the tracked campaigns are stored, selected via the `storage` config key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// A SQLite database at `.storage/campaigns.db`
+    #[default]
+    Sqlite,
+    /// A `.campaign` json file per campaign in `.storage/campaigns`
+    Json,
+}
+
+impl StorageBackend {
+    /// Parses `sqlite` or `json`
+    pub fn parse(backend: &str) -> Result<StorageBackend> {
+        match backend.trim().to_lowercase().as_str() {
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            "json" => Ok(StorageBackend::Json),
+            _ => Err(ErgoCfError::InvalidConfig(format!("`{}` is not a valid storage backend, use either `sqlite` or `json`.", backend))),
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageBackend::Sqlite => write!(f, "sqlite"),
+            StorageBackend::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The on-chain status of a Campaign as it was seen by a `status` check.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub height: u64,
+    pub raised: NanoErg,
+    pub goal: NanoErg,
+    pub state: CampaignState,
+}
+
+impl StatusSnapshot {
+    /// Snapshot of the status taken now
+    pub fn new(status: &CampaignStatus) -> StatusSnapshot {
+        StatusSnapshot {
+            taken_at: Utc::now(),
+            height: status.height,
+            raised: status.raised,
+            goal: status.goal,
+            state: status.state.clone(),
+        }
+    }
+}
+
+/// Local storage of the tracked Campaigns together with their backing txs, refunds and status history.
+pub trait CampaignStore {
+    /// All tracked campaigns, ordered by name
+    fn list(&self) -> Result<Vec<Box<dyn CrowdfundingCampaign>>>;
+    /// The tracked campaign with the given name
+    fn get(&self, name: &str) -> Result<Option<Box<dyn CrowdfundingCampaign>>>;
+    /// Saves the campaign, replacing the tracked campaign of the same name
+    fn put(&self, camp: &dyn CrowdfundingCampaign) -> Result<()>;
+    /// Stops tracking the campaign with the given name, removing its status history
+    fn delete(&self, name: &str) -> Result<()>;
+    /// Adds a snapshot of the on-chain status to the history of the campaign with the given name
+    fn put_status(&self, name: &str, status: &CampaignStatus) -> Result<()>;
+    /// The status snapshots of the campaign with the given name, oldest first
+    fn history(&self, name: &str) -> Result<Vec<StatusSnapshot>>;
+}
+
+/// A `Campaign` or `BackedCampaign` held by value
+#[derive(Clone)]
+enum StoredCampaign {
+    Campaign(Campaign),
+    Backed(BackedCampaign),
+}
+
+impl StoredCampaign {
+    fn new(camp: &dyn CrowdfundingCampaign) -> StoredCampaign {
+        match camp.as_backed() {
+            Some(backed_camp) => StoredCampaign::Backed(backed_camp.clone()),
+            None => StoredCampaign::Campaign(camp.get_campaign().clone()),
+        }
+    }
+
+    fn boxed(self) -> Box<dyn CrowdfundingCampaign> {
+        match self {
+            StoredCampaign::Campaign(camp) => Box::new(camp),
+            StoredCampaign::Backed(backed_camp) => Box::new(backed_camp),
+        }
+    }
+}
+
+fn not_stored(name: &str) -> ErgoCfError {
+    ErgoCfError::Storage(format!("No campaign named `{}` is stored.", name))
+}
+
+
+/// `CampaignStore` keeping everything in a SQLite database.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) the database at `path`. `:memory:` opens a database which only lives as long as the `SqliteStore`.
+    pub fn open(path: &str) -> Result<SqliteStore> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(SqliteStore { conn })
+    }
+
+    /// Imports the `.campaign` json files from `folder`, which is only done the first time the database is used. Returns the number of imported campaigns.
+    pub fn import_campaign_files(&self, folder: &str) -> Result<usize> {
+        let version : i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(0);
+        }
+        let camps = JsonDirStore::new(folder).list()?;
+        for camp in &camps {
+            self.put(camp.as_ref())?;
+        }
+        self.conn.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+        Ok(camps.len())
+    }
+
+    /// Reads the campaigns matching the `WHERE` clause, together with their backing txs
+    fn select_campaigns(&self, filter: &str, name: Option<&str>) -> Result<Vec<Box<dyn CrowdfundingCampaign>>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT name, address, deadline, deadline_target, goal_nanoerg, network, scan_id, collect_tx_id, backer_address, p2s_address
+             FROM campaigns {} ORDER BY name", filter))?;
+        let rows = match name {
+            Some(name) => stmt.query_map(params![name], campaign_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?,
+            None => stmt.query_map([], campaign_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?,
+        };
+        let mut campaigns : Vec<Box<dyn CrowdfundingCampaign>> = vec![];
+        for (camp, backer_address, p2s_address) in rows {
+            match (backer_address, p2s_address) {
+                (Some(backer_address), Some(p2s_address)) => {
+                    let backer_txs = self.backing_txs(&camp.name)?;
+                    campaigns.push(Box::new(BackedCampaign::new(camp, backer_address, p2s_address, backer_txs)));
+                }
+                _ => campaigns.push(Box::new(camp)),
+            }
+        }
+        Ok(campaigns)
+    }
+
+    /// Reads the backing txs of the campaign, in the order they were made
+    fn backing_txs(&self, name: &str) -> Result<Vec<BackingTx>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.tx_id, t.backed_amount, t.fee, t.status, t.confirmed_height, t.checked_height, r.refund_tx_id
+             FROM backing_txs t LEFT JOIN refunds r ON r.campaign_name = t.campaign_name AND r.backing_tx_id = t.tx_id
+             WHERE t.campaign_name = ?1 ORDER BY t.position")?;
+        let rows = stmt.query_map(params![name], |row| {
+            let status : String = row.get(3)?;
+            let confirmed_height : Option<i64> = row.get(4)?;
+            Ok((BackingTx {
+                tx_id: row.get(0)?,
+                backed_amount: NanoErg(row.get::<_, i64>(1)? as u64),
+                fee: NanoErg(row.get::<_, i64>(2)? as u64),
+                refund_tx_id: row.get(6)?,
+                status: TxStatus::Pending,
+                checked_height: row.get::<_, Option<i64>>(5)?.map(|h| h as u64),
+            }, status, confirmed_height))
+        })?;
+        let mut txs = vec![];
+        for row in rows {
+            let (mut tx, status, confirmed_height) = row?;
+            tx.status = match (status.as_str(), confirmed_height) {
+                ("confirmed", Some(height)) => TxStatus::Confirmed(height as u64),
+                ("dropped", _) => TxStatus::Dropped,
+                _ => TxStatus::Pending,
+            };
+            txs.push(tx);
+        }
+        Ok(txs)
+    }
+}
+
+/// Reads a `Campaign` from a row of the `campaigns` table, along with the backer & P2S address if it was backed
+fn campaign_from_row(row: &Row) -> rusqlite::Result<(Campaign, Option<String>, Option<String>)> {
+    let network : String = row.get(5)?;
+    let deadline_target : Option<String> = row.get(3)?;
+    let camp = Campaign {
+        name: row.get(0)?,
+        address: row.get(1)?,
+        deadline: row.get::<_, i64>(2)? as u64,
+        deadline_target: deadline_target.and_then(|t| DateTime::parse_from_rfc3339(&t).ok()).map(|t| t.with_timezone(&Utc)),
+        goal: NanoErg(row.get::<_, i64>(4)? as u64),
+        network: Network::parse(&network).unwrap_or_default(),
+        scan_id: row.get::<_, Option<i64>>(6)?.map(|id| id as u64),
+        collect_tx_id: row.get(7)?,
+    };
+    Ok((camp, row.get(8)?, row.get(9)?))
+}
+
+impl CampaignStore for SqliteStore {
+    fn list(&self) -> Result<Vec<Box<dyn CrowdfundingCampaign>>> {
+        self.select_campaigns("", None)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Box<dyn CrowdfundingCampaign>>> {
+        Ok(self.select_campaigns("WHERE name = ?1", Some(name))?.pop())
+    }
+
+    fn put(&self, camp: &dyn CrowdfundingCampaign) -> Result<()> {
+        let backed = camp.as_backed();
+        let c = camp.get_campaign();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO campaigns (name, address, deadline, deadline_target, goal_nanoerg, network, scan_id, collect_tx_id, backer_address, p2s_address)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(name) DO UPDATE SET address = ?2, deadline = ?3, deadline_target = ?4, goal_nanoerg = ?5, network = ?6,
+                scan_id = ?7, collect_tx_id = ?8, backer_address = ?9, p2s_address = ?10",
+            params![c.name, c.address, c.deadline as i64, c.deadline_target.map(|t| t.to_rfc3339()), c.goal.0 as i64, c.network.to_string(),
+                c.scan_id.map(|id| id as i64), c.collect_tx_id, backed.map(|b| &b.backer_address), backed.map(|b| &b.p2s_address)])?;
+        tx.execute("DELETE FROM backing_txs WHERE campaign_name = ?1", params![c.name])?;
+        for (position, backing_tx) in backed.map(|b| b.backer_txs.as_slice()).unwrap_or_default().iter().enumerate() {
+            let (status, confirmed_height) = match backing_tx.status {
+                TxStatus::Pending => ("pending", None),
+                TxStatus::Confirmed(height) => ("confirmed", Some(height as i64)),
+                TxStatus::Dropped => ("dropped", None),
+            };
+            tx.execute(
+                "INSERT INTO backing_txs (campaign_name, tx_id, position, backed_amount, fee, status, confirmed_height, checked_height)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![c.name, backing_tx.tx_id, position as i64, backing_tx.backed_amount.0 as i64, backing_tx.fee.0 as i64, status, confirmed_height,
+                    backing_tx.checked_height.map(|h| h as i64)])?;
+            if let Some(refund_tx_id) = &backing_tx.refund_tx_id {
+                tx.execute("INSERT INTO refunds (campaign_name, backing_tx_id, refund_tx_id) VALUES (?1, ?2, ?3)",
+                    params![c.name, backing_tx.tx_id, refund_tx_id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        if self.conn.execute("DELETE FROM campaigns WHERE name = ?1", params![name])? == 0 {
+            return Err(not_stored(name));
+        }
+        Ok(())
+    }
+
+    fn put_status(&self, name: &str, status: &CampaignStatus) -> Result<()> {
+        let snapshot = StatusSnapshot::new(status);
+        let state = serde_json::to_value(&snapshot.state).map_err(|e| ErgoCfError::Storage(e.to_string()))?;
+        self.conn.execute(
+            "INSERT INTO status_snapshots (campaign_name, taken_at, height, raised, goal, state) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![name, snapshot.taken_at.to_rfc3339(), snapshot.height as i64, snapshot.raised.0 as i64, snapshot.goal.0 as i64, state.as_str().unwrap_or_default()])?;
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> Result<Vec<StatusSnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT taken_at, height, raised, goal, state FROM status_snapshots WHERE campaign_name = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![name], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?, row.get::<_, String>(4)?))
+        })?;
+        let mut snapshots = vec![];
+        for row in rows {
+            let (taken_at, height, raised, goal, state) = row?;
+            let bad_row = |e: String| ErgoCfError::Storage(format!("Invalid status snapshot of `{}`: {}", name, e));
+            snapshots.push(StatusSnapshot {
+                taken_at: DateTime::parse_from_rfc3339(&taken_at).map_err(|e| bad_row(e.to_string()))?.with_timezone(&Utc),
+                height: height as u64,
+                raised: NanoErg(raised as u64),
+                goal: NanoErg(goal as u64),
+                state: serde_json::from_value(serde_json::Value::String(state)).map_err(|e| bad_row(e.to_string()))?,
+            });
+        }
+        Ok(snapshots)
+    }
+}
+
+
+/// `CampaignStore` keeping a `.campaign` json file per campaign in a folder, next to a `.history` file of json lines with its status snapshots.
+pub struct JsonDirStore {
+    folder: String,
+}
+
+impl JsonDirStore {
+    /// Store in the given folder, which must already exist
+    pub fn new(folder: &str) -> JsonDirStore {
+        let mut folder = folder.to_string();
+        if !folder.ends_with('/') {
+            folder.push('/');
+        }
+        JsonDirStore { folder }
+    }
+
+    fn file_path(&self, name: &str, extension: &str) -> String {
+        campaign_file_path(&self.folder, name, extension)
+    }
+}
+
+/// Reads a `Campaign` or `BackedCampaign` json file, skipping files which are neither
+fn read_campaign_file(path: &Path) -> Result<Option<StoredCampaign>> {
+    let file = File::open(path)?;
+    let campaign : Option<Campaign> =  serde_json::from_reader(file).ok();
+    if let Some(camp) = campaign {
+        return Ok(Some(StoredCampaign::Campaign(camp)));
+    }
+    let file = File::open(path)?;
+    let backedcampaign : Option<BackedCampaign> = serde_json::from_reader(file).ok();
+    Ok(backedcampaign.map(StoredCampaign::Backed))
+}
+
+impl CampaignStore for JsonDirStore {
+    fn list(&self) -> Result<Vec<Box<dyn CrowdfundingCampaign>>> {
+        let mut campaigns : Vec<Box<dyn CrowdfundingCampaign>> = vec![];
+        if let Ok(rd) = read_dir(&self.folder){
+            for entry in rd.flatten() {
+                if !entry.file_name().to_string_lossy().ends_with(CAMPAIGN_EXTENSION) {
+                    continue;
+                }
+                if let Some(camp) = read_campaign_file(&entry.path())? {
+                    campaigns.push(camp.boxed());
+                }
+            }
+        }
+        campaigns.sort_by(|a, b| a.get_campaign().name.cmp(&b.get_campaign().name));
+        Ok(campaigns)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Box<dyn CrowdfundingCampaign>>> {
+        let path = self.file_path(name, CAMPAIGN_EXTENSION);
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+        let camp = read_campaign_file(Path::new(&path))?.map(|camp| camp.boxed());
+        Ok(camp.filter(|camp| camp.get_campaign().name == name))
+    }
+
+    fn put(&self, camp: &dyn CrowdfundingCampaign) -> Result<()> {
+        camp.save(&mut self.folder.clone())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        if self.get(name)?.is_none() {
+            return Err(not_stored(name));
+        }
+        remove_file(self.file_path(name, CAMPAIGN_EXTENSION))?;
+        remove_file(self.file_path(name, HISTORY_EXTENSION)).ok();
+        Ok(())
+    }
+
+    fn put_status(&self, name: &str, status: &CampaignStatus) -> Result<()> {
+        if self.get(name)?.is_none() {
+            return Err(not_stored(name));
+        }
+        let line = serde_json::to_string(&StatusSnapshot::new(status)).map_err(|e| ErgoCfError::Storage(e.to_string()))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(self.file_path(name, HISTORY_EXTENSION))?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> Result<Vec<StatusSnapshot>> {
+        let text = match read_to_string(self.file_path(name, HISTORY_EXTENSION)) {
+            Ok(text) => text,
+            Err(_) => return Ok(vec![]),
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| ErgoCfError::Storage(format!("Invalid status snapshot of `{}`: {}", name, e))))
+            .collect()
+    }
+}
+
+
+/// `CampaignStore` which only keeps the campaigns in memory, for tests and for using the library without touching the disk.
+#[derive(Default)]
+pub struct MemoryStore {
+    campaigns: RefCell<BTreeMap<String, StoredCampaign>>,
+    history: RefCell<BTreeMap<String, Vec<StatusSnapshot>>>,
+}
+
+impl MemoryStore {
+    /// An empty store
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl CampaignStore for MemoryStore {
+    fn list(&self) -> Result<Vec<Box<dyn CrowdfundingCampaign>>> {
+        Ok(self.campaigns.borrow().values().cloned().map(StoredCampaign::boxed).collect())
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Box<dyn CrowdfundingCampaign>>> {
+        Ok(self.campaigns.borrow().get(name).cloned().map(StoredCampaign::boxed))
+    }
+
+    fn put(&self, camp: &dyn CrowdfundingCampaign) -> Result<()> {
+        self.campaigns.borrow_mut().insert(camp.get_campaign().name.clone(), StoredCampaign::new(camp));
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        self.campaigns.borrow_mut().remove(name).ok_or_else(|| not_stored(name))?;
+        self.history.borrow_mut().remove(name);
+        Ok(())
+    }
+
+    fn put_status(&self, name: &str, status: &CampaignStatus) -> Result<()> {
+        if !self.campaigns.borrow().contains_key(name) {
+            return Err(not_stored(name));
+        }
+        self.history.borrow_mut().entry(name.to_string()).or_default().push(StatusSnapshot::new(status));
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> Result<Vec<StatusSnapshot>> {
+        Ok(self.history.borrow().get(name).cloned().unwrap_or_default())
+    }
+}
+
+
+/// Opens the local storage in the data dir using the given backend. The SQLite database imports the `.campaign` files stored by earlier versions the first time.
+pub fn open_store(backend: StorageBackend, data_dir: &str) -> Result<Box<dyn CampaignStore>> {
+    match backend {
+        StorageBackend::Sqlite => {
+            let store = SqliteStore::open(&database_path(data_dir))?;
+            store.import_campaign_files(&campaigns_folder(data_dir))?;
+            Ok(Box::new(store))
+        }
+        StorageBackend::Json => Ok(Box::new(JsonDirStore::new(&campaigns_folder(data_dir)))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deadline::Deadline;
+    use crate::wallet_reqs::TX_FEE;
+
+    const CREATOR : &str = "9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdf";
+    const BACKER : &str = "9giqagqgA7ix97nmFUhrty2smh7gisuEudHZgkT6o1QAUiRauCe";
+
+    /// Runs the same checks against every `CampaignStore`
+    fn check_store(store: &dyn CampaignStore) {
+        let camp = Campaign::new("Test", CREATOR, Deadline::at_height(1000), "2", Network::Mainnet).unwrap();
+        store.put(&camp).unwrap();
+        let mut txs = vec![BackingTx::new("a".to_string(), NanoErg(1500000000), NanoErg(TX_FEE)), BackingTx::new("b".to_string(), NanoErg(7), NanoErg(TX_FEE))];
+        txs[0].status = TxStatus::Confirmed(900);
+        txs[0].checked_height = Some(910);
+        txs[1].refund_tx_id = Some("refund".to_string());
+        let other = Campaign::new("Other", CREATOR, Deadline::at_height(1000), "1", Network::Mainnet).unwrap();
+        store.put(&BackedCampaign::new(other, BACKER.to_string(), "p2s".to_string(), txs)).unwrap();
+        store.put_status("Test", &camp.status_from_boxes(900, &[])).unwrap();
+        store.put_status("Test", &camp.status_from_boxes(1000, &[])).unwrap();
+        assert!(store.put_status("Missing", &camp.status_from_boxes(900, &[])).is_err());
+
+        let camps = store.list().unwrap();
+        assert_eq!(camps.len(), 2);
+        let backed = camps[0].as_backed().unwrap();
+        assert_eq!(backed.campaign.name, "Other");
+        assert_eq!(backed.backer_txs.iter().map(|tx| tx.tx_id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(backed.backer_txs[0].status, TxStatus::Confirmed(900));
+        assert_eq!(backed.backer_txs[1].refund_tx_id.as_deref(), Some("refund"));
+        assert_eq!(camps[1].get_campaign().goal, NanoErg(2000000000));
+        assert!(camps[1].as_backed().is_none());
+        assert_eq!(store.get("Test").unwrap().unwrap().get_campaign().goal, NanoErg(2000000000));
+        assert!(store.get("Missing").unwrap().is_none());
+        let history = store.history("Test").unwrap();
+        assert_eq!(history.iter().map(|s| (s.height, s.state.clone())).collect::<Vec<_>>(), vec![(900, CampaignState::Active), (1000, CampaignState::Failed)]);
+
+        store.put(&BackedCampaign::new(camp, BACKER.to_string(), "p2s".to_string(), vec![])).unwrap();
+        assert!(store.get("Test").unwrap().unwrap().as_backed().is_some());
+        store.delete("Test").unwrap();
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.history("Test").unwrap().is_empty());
+        assert!(store.delete("Test").is_err());
+    }
+
+    #[test]
+    fn campaign_stores_are_valid() {
+        check_store(&SqliteStore::open(":memory:").unwrap());
+        check_store(&MemoryStore::new());
+        let dir = tempfile::tempdir().unwrap();
+        check_store(&JsonDirStore::new(dir.path().to_str().unwrap()));
+    }
+
+    #[test]
+    fn campaign_file_import_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = format!(r#"{{"name": "Legacy", "address": "{}", "deadline": 1000, "goal": 2}}"#, CREATOR);
+        std::fs::write(dir.path().join("Legacy.campaign"), legacy).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a campaign").unwrap();
+        let folder = dir.path().to_str().unwrap();
+
+        let store = SqliteStore::open(dir.path().join("campaigns.db").to_str().unwrap()).unwrap();
+        assert_eq!(store.import_campaign_files(folder).unwrap(), 1);
+        assert_eq!(store.list().unwrap()[0].get_campaign().goal, NanoErg(2000000000));
+        store.delete("Legacy").unwrap();
+        assert_eq!(store.import_campaign_files(folder).unwrap(), 0);
+        assert!(store.list().unwrap().is_empty());
+    }
+}
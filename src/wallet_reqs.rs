@@ -1,147 +1,397 @@
-use crate::campaign::{CrowdfundingCampaign, Campaign, BackingTx};
-use handlebars::Handlebars;
-use reqwest;
+use crate::address::{Network, address_network, validate_address};
+use crate::error::{ErgoCfError, Result};
+use reqwest::StatusCode;
 use reqwest::header::{HeaderValue, CONTENT_TYPE};
-use serde::Deserialize;
-use std::io::prelude::*;
-use std::fs::{File};
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::sync::Mutex;
+use std::time::Duration;
 
-static SEND_PAYMENT_TEMPLATE : &'static str = r#"[{"address":"{{address}}","value":{{value}} }]"#;
+/// Default fee in nanoErg paid for every transaction sent by the CLI tool, which is also the node's default fee for wallet payments
+pub const TX_FEE : u64 = 1000000;
 
 #[derive(Deserialize)]
 struct P2SAddress {
     address: String
 }
 
-/// Gets node ip from local file `node.ip`
-pub fn get_node_ip() -> String {
-    let mut file = File::open("node.ip").expect("Failed to open node ip file.");
-    let mut st = String::new();
-    file.read_to_string(&mut st).ok().expect("Failed to read node ip from file.");
-    st.trim().to_string()
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeInfo {
+    full_height: Option<u64>
 }
 
-/// Gets list of addresses and asks the user to select one
-pub fn select_wallet_address(api_key: &String) -> String {
-    let address_list = get_wallet_addresses(api_key);
-    if address_list.len() == 1 {
-        return address_list[0].clone();
-    }
+#[derive(Deserialize)]
+struct WalletBalance {
+    balance: u64
+}
+
+#[derive(Deserialize)]
+struct BoxBytes {
+    bytes: String
+}
+
+#[derive(Deserialize)]
+struct AddressRaw {
+    raw: String
+}
+
+#[derive(Deserialize)]
+struct AddressTree {
+    tree: String
+}
 
-    let mut n = 0;
-    for address in &address_list {
-        n += 1;
-        println!("{}. {}", n, address);
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanId {
+    scan_id: u64
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexedTx {
+    inclusion_height: u64
+}
+
+#[derive(Deserialize)]
+struct ScanBox {
+    #[serde(rename = "box")]
+    ergo_box: NodeBox
+}
+
+/// Datatype which holds the relevant fields of an unspent box as returned by the node.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeBox {
+    pub box_id: String,
+    pub value: u64,
+    pub ergo_tree: String,
+    pub transaction_id: String,
+}
+
+/// Confirmation state of a transaction as seen by the node: waiting in the mempool, included at a height, or dropped (neither mined nor in the mempool).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    #[default]
+    Pending,
+    Confirmed(u64),
+    Dropped,
+}
+
+/// Abstraction over every request the CLI tool makes to an Ergo node.
+pub trait NodeClient {
+    fn wallet_addresses(&self) -> Result<Vec<String>>;
+    fn p2s_address(&self, script: &str) -> Result<String>;
+    fn wallet_balance(&self) -> Result<u64>;
+    fn send_payment(&self, address: &str, value: u64, fee: u64) -> Result<String>;
+    fn send_transaction(&self, address: &str, value: u64, fee: u64, inputs_raw: &[String]) -> Result<String>;
+    fn current_height(&self) -> Result<u64>;
+    fn unspent_boxes(&self, address: &str) -> Result<Vec<NodeBox>>;
+    fn box_bytes(&self, box_id: &str) -> Result<String>;
+    fn address_raw(&self, address: &str) -> Result<String>;
+    fn address_tree(&self, address: &str) -> Result<String>;
+    fn register_box_scan(&self, scan_name: &str, pk_raw: &str) -> Result<u64>;
+    fn scan_unspent_boxes(&self, scan_id: u64) -> Result<Vec<NodeBox>>;
+    fn tx_status(&self, tx_id: &str) -> Result<TxStatus>;
+}
+
+/// Replaces the api key in recorded fixtures
+const REDACTED : &str = "********";
+
+/// A request made to the node together with the node's response, as stored in a fixture file. The api key is always redacted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeExchange {
+    pub method: String,
+    pub endpoint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+/// How a `ReqwestNodeClient` gets its responses.
+enum NodeMode {
+    /// Sends every request to the node
+    Live,
+    /// Sends every request to the node and saves each exchange into the fixture file at the path
+    Record(String, Mutex<Vec<NodeExchange>>),
+    /// Answers every request from the exchanges of a fixture file without contacting the node
+    Replay(Mutex<Vec<NodeExchange>>),
+}
+
+/// Status and body of a response from the node
+struct NodeResponse {
+    status: u16,
+    body: String,
+}
+
+impl NodeResponse {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
     }
-    println!("Which address would you like to select?");
-    let mut input = String::new();
-    if let Ok(_) = std::io::stdin().read_line(&mut input){
-        if let Ok(input_n) = input.trim().parse::<usize>(){
-            if input_n > address_list.len() || input_n < 1 {
-                println!("Please select an address within the range.");
-                return select_wallet_address(api_key);
-            }
-            return address_list[input_n-1].clone();
 
-        }
+    /// Parses the json body of the response
+    fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.body).map_err(|e| ErgoCfError::BadNodeResponse(format!("{}: {}", e, self.body)))
     }
-    return select_wallet_address(api_key);
 }
 
-/// Gets a list of all addresses from the local unlocked node wallet
-pub fn get_wallet_addresses(api_key: &String) -> Vec<String> {
-    let endpoint = get_node_ip() + "/wallet/addresses";
-    let client = reqwest::Client::new();
-    let hapi_key = HeaderValue::from_str(&api_key).expect("Failed to create header value from api key.");
-    let mut res = client.get(&endpoint)
-                .header("accept", "application/json")
-                .header("api_key", hapi_key)
-                .header(CONTENT_TYPE, "application/json")
-                .send()
-                .expect("Failed to send request to local node. Please make sure it is running on the IP & Port specified in `node.ip` file.");
+/// `NodeClient` which talks to the node REST api over http via `reqwest`. Exchanges with the node can be recorded into a fixture file and later replayed from it.
+pub struct ReqwestNodeClient {
+    node_ip: String,
+    api_key: String,
+    client: reqwest::Client,
+    mode: NodeMode,
+}
 
+impl ReqwestNodeClient {
+    /// Create a new `ReqwestNodeClient` for the node at `node_ip`, giving up on requests after `timeout` seconds
+    pub fn new(node_ip: &str, api_key: &str, timeout: u64) -> Result<ReqwestNodeClient> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .build()?;
+        Ok(ReqwestNodeClient {
+            node_ip: node_ip.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            client,
+            mode: NodeMode::Live,
+        })
+    }
 
-    let mut addresses : Vec<String> = vec![];
-    for segment in res.text().expect("Failed to get addresses from wallet.").split("\""){
-        let seg = segment.trim();
-        if seg.chars().next().unwrap() == '9' {
-           addresses.push(seg.to_string()); 
-        }
+    /// Records every exchange with the node into the fixture file at `path`, with the api key redacted
+    pub fn record_to(mut self, path: &str) -> ReqwestNodeClient {
+        self.mode = NodeMode::Record(path.to_string(), Mutex::new(vec![]));
+        self
     }
-    if addresses.len() == 0 {
-        panic!("No addresses were found. Please make sure it is running on the IP & Port specified in `node.ip` file and that your wallet is unlocked.");
+
+    /// Create a `ReqwestNodeClient` which never contacts a node, answering every request with the recorded response from the fixture file at `path`.
+    /// Each recorded exchange is used once, in the order they were recorded.
+    pub fn replay_from(path: &str) -> Result<ReqwestNodeClient> {
+        let file = File::open(path)?;
+        let exchanges : Vec<NodeExchange> = serde_json::from_reader(file).map_err(|e| ErgoCfError::Storage(format!("Failed to read the fixture `{}`: {}", path, e)))?;
+        let mut node = ReqwestNodeClient::new("", "", 0)?;
+        node.mode = NodeMode::Replay(Mutex::new(exchanges));
+        Ok(node)
     }
-    addresses
-}
 
-/// Get P2S Address for Backer to submit to for the Campaign
-pub fn get_p2s_address(api_key: &String, campaign: &Campaign,  backer_address: &String) -> String {
-    let endpoint = get_node_ip() + "/script/p2sAddress";
-    let client = reqwest::Client::new();
-    let hapi_key = HeaderValue::from_str(&api_key).expect("Failed to create header value from api key.");
-    let mut res = client.post(&endpoint)
-                .header("accept", "application/json")
-                .header("api_key", hapi_key)
+    /// Sends a request to the node endpoint without checking the response status. Requests with a json `body` are POSTs, all others GETs.
+    fn send(&self, endpoint: &str, body: Option<String>) -> Result<NodeResponse> {
+        let method = if body.is_some() { "POST" } else { "GET" };
+        if let NodeMode::Replay(exchanges) = &self.mode {
+            return replay_exchange(exchanges, method, endpoint, body.as_deref());
+        }
+        let hapi_key = HeaderValue::from_str(&self.api_key).map_err(|_| ErgoCfError::AuthFailure)?;
+        let url = self.node_ip.clone() + endpoint;
+        let request = match &body {
+            Some(body) => self.client.post(&url)
                 .header(CONTENT_TYPE, "application/json")
-                .body(campaign.build_script(backer_address))
-                .send()
-                .expect("Failed to send request to local node. Please make sure it is running on the IP & Port specified in `node.ip` file and that your wallet is unlocked.");
-
-    if let Ok(p2saddress) = res.json::<P2SAddress>() {
-        return p2saddress.address;
-    }
-    else if let Err(e) = res.json::<P2SAddress>() {
-        println!("{:?}", e);
-        let err = res.text().expect("P2S Address error.");
-        println!("P2S address node error: {:?}", err);
-    }
-    panic!("Failed to acquire P2S Address. Make sure your node is running and that the data you provided is valid.");
-}
-
-/// Send payment from unlocked wallet to given address via local node api. Returns the box identifier.
-pub fn send_wallet_payment(api_key: &String, address: &String, amount: f64) -> Option<BackingTx> {
-    let json_body = json!({ "address": address,
-                            "value": erg_to_nanoerg(amount) });
-    let reg = Handlebars::new();
-    let pb = reg.render_template(SEND_PAYMENT_TEMPLATE, &json_body).ok()?;
-    let endpoint = get_node_ip() + "/wallet/payment/send";
-    let client = reqwest::Client::new();
-    let hapi_key = HeaderValue::from_str(&api_key).expect("Failed to create header value from api key.");
-    let res = client.post(&endpoint)
+                .body(body.clone()),
+            None => self.client.get(&url),
+        };
+        let mut res = request
                 .header("accept", "application/json")
                 .header("api_key", hapi_key)
-                .header(CONTENT_TYPE, "application/json")
-                .body(pb)
-                .send();
+                .send()?;
+        let response = NodeResponse { status: res.status().as_u16(), body: res.text()? };
+        if let NodeMode::Record(path, exchanges) = &self.mode {
+            self.record_exchange(path, exchanges, method, endpoint, body, &response)?;
+        }
+        Ok(response)
+    }
 
-    let mut tx_id = res.ok()?.text().ok()?;
-    tx_id.retain(|c| c != '"');
+    /// Adds the exchange to the recorded ones and saves them all into the fixture file, redacting the api key
+    fn record_exchange(&self, path: &str, exchanges: &Mutex<Vec<NodeExchange>>, method: &str, endpoint: &str, body: Option<String>, response: &NodeResponse) -> Result<()> {
+        let redact = |text: &str| if self.api_key.is_empty() { text.to_string() } else { text.replace(&self.api_key, REDACTED) };
+        let mut headers = BTreeMap::new();
+        headers.insert("accept".to_string(), "application/json".to_string());
+        headers.insert("api_key".to_string(), REDACTED.to_string());
+        if body.is_some() {
+            headers.insert(CONTENT_TYPE.to_string(), "application/json".to_string());
+        }
+        let mut exchanges = exchanges.lock().map_err(|_| ErgoCfError::Storage("Failed to record the node exchange.".to_string()))?;
+        exchanges.push(NodeExchange {
+            method: method.to_string(),
+            endpoint: redact(endpoint),
+            request_body: body.as_deref().map(redact),
+            headers,
+            status: response.status,
+            response_body: redact(&response.body),
+        });
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, &*exchanges).map_err(|e| ErgoCfError::Storage(e.to_string()))
+    }
+
+    /// Sends a GET request to the node endpoint
+    fn get(&self, endpoint: &str) -> Result<NodeResponse> {
+        check_response(self.send(endpoint, None)?)
+    }
 
-    if tx_id.contains("bad.request") {
-        println!("Failed to make payment. This is the error from the ergo node/wallet:\n{}", tx_id);
-        std::process::exit(0);
+    /// Sends a GET request to the node endpoint, where a 404 means the item does not exist rather than an error
+    fn get_found(&self, endpoint: &str) -> Result<Option<NodeResponse>> {
+        let res = self.send(endpoint, None)?;
+        if res.status == StatusCode::NOT_FOUND.as_u16() {
+            return Ok(None);
+        }
+        check_response(res).map(Some)
+    }
+
+    /// Sends a POST request with a json body to the node endpoint
+    fn post(&self, endpoint: &str, body: String) -> Result<NodeResponse> {
+        check_response(self.send(endpoint, Some(body))?)
     }
+}
+
+/// Takes the first recorded exchange for the request out of the fixture, preferring one with the same request body
+fn replay_exchange(exchanges: &Mutex<Vec<NodeExchange>>, method: &str, endpoint: &str, body: Option<&str>) -> Result<NodeResponse> {
+    let mut exchanges = exchanges.lock().map_err(|_| ErgoCfError::Storage("Failed to read the replay fixture.".to_string()))?;
+    let matches_request = |e: &NodeExchange| e.method == method && e.endpoint == endpoint;
+    let i = exchanges.iter().position(|e| matches_request(e) && e.request_body.as_deref() == body)
+        .or_else(|| exchanges.iter().position(matches_request))
+        .ok_or_else(|| ErgoCfError::BadNodeResponse(format!("The replay fixture holds no (more) recorded responses for {} {}.", method, endpoint)))?;
+    let exchange = exchanges.remove(i);
+    Ok(NodeResponse { status: exchange.status, body: exchange.response_body })
+}
 
-    return Some(BackingTx::new(tx_id, amount));
+/// Turns unsuccessful node responses into the matching `ErgoCfError`
+fn check_response(res: NodeResponse) -> Result<NodeResponse> {
+    if res.is_success() {
+        return Ok(res);
+    }
+    if res.status == StatusCode::UNAUTHORIZED.as_u16() || res.status == StatusCode::FORBIDDEN.as_u16() {
+        return Err(ErgoCfError::AuthFailure);
+    }
+    if res.body.to_lowercase().contains("locked") {
+        return Err(ErgoCfError::WalletLocked);
+    }
+    let status = StatusCode::from_u16(res.status).map(|s| s.to_string()).unwrap_or_else(|_| res.status.to_string());
+    Err(ErgoCfError::BadNodeResponse(format!("{} {}", status, res.body)))
 }
 
-/// Convert from Erg to nanoErg
-pub fn erg_to_nanoerg(erg_amount: f64) -> u64 {
-    (erg_amount * 1000000000 as f64) as u64
+/// Reads a transaction id returned as a json string by the node
+fn read_tx_id(res: NodeResponse) -> String {
+    let mut tx_id = res.body;
+    tx_id.retain(|c| c != '"');
+    tx_id.trim().to_string()
 }
 
+impl NodeClient for ReqwestNodeClient {
+    /// Gets a list of all addresses from the local unlocked node wallet
+    fn wallet_addresses(&self) -> Result<Vec<String>> {
+        let addresses : Vec<String> = self.get("/wallet/addresses")?.json()?;
+        if addresses.is_empty() {
+            return Err(ErgoCfError::WalletLocked);
+        }
+        Ok(addresses)
+    }
+
+    /// Compiles the script and gets its P2S Address
+    fn p2s_address(&self, script: &str) -> Result<String> {
+        let p2saddress : P2SAddress = self.post("/script/p2sAddress", script.to_string())?.json()?;
+        Ok(p2saddress.address)
+    }
+
+    /// Gets the confirmed balance of the unlocked wallet in nanoErg
+    fn wallet_balance(&self) -> Result<u64> {
+        let balance : WalletBalance = self.get("/wallet/balances")?.json()?;
+        Ok(balance.balance)
+    }
+
+    /// Send payment from unlocked wallet to given address, paying `fee`. The wallet selects the inputs. Returns the tx id.
+    fn send_payment(&self, address: &str, value: u64, fee: u64) -> Result<String> {
+        let json_body = json!({ "requests": [{ "address": address, "value": value }],
+                                "fee": fee });
+        Ok(read_tx_id(self.post("/wallet/transaction/send", json_body.to_string())?))
+    }
+
+    /// Spends the given raw inputs to a single output at `address` via the unlocked node wallet. Returns the tx id.
+    fn send_transaction(&self, address: &str, value: u64, fee: u64, inputs_raw: &[String]) -> Result<String> {
+        let json_body = json!({ "requests": [{ "address": address, "value": value }],
+                                "fee": fee,
+                                "inputsRaw": inputs_raw,
+                                "dataInputsRaw": [] });
+        Ok(read_tx_id(self.post("/wallet/transaction/send", json_body.to_string())?))
+    }
+
+    /// Gets the current height of the blockchain
+    fn current_height(&self) -> Result<u64> {
+        let info : NodeInfo = self.get("/info")?.json()?;
+        info.full_height.ok_or_else(|| ErgoCfError::BadNodeResponse("The node has not synced any blocks yet. Please wait for it to sync and try again.".to_string()))
+    }
+
+    /// Gets all unspent boxes locked at the given address
+    fn unspent_boxes(&self, address: &str) -> Result<Vec<NodeBox>> {
+        self.post("/blockchain/box/unspent/byAddress", json!(address).to_string())?.json()
+    }
+
+    /// Gets the serialized bytes of a box, used as a raw input when building a transaction
+    fn box_bytes(&self, box_id: &str) -> Result<String> {
+        let box_bytes : BoxBytes = self.get(&("/utxo/byIdBinary/".to_string() + box_id))?.json()?;
+        Ok(box_bytes.bytes)
+    }
+
+    /// Gets the raw public key hex encoded inside of a P2PK address
+    fn address_raw(&self, address: &str) -> Result<String> {
+        let address_raw : AddressRaw = self.get(&("/utils/addressToRaw/".to_string() + address))?.json()?;
+        Ok(address_raw.raw)
+    }
+
+    /// Gets the hex encoded ErgoTree of an address
+    fn address_tree(&self, address: &str) -> Result<String> {
+        let address_tree : AddressTree = self.get(&("/script/addressToTree/".to_string() + address))?.json()?;
+        Ok(address_tree.tree)
+    }
+
+    /// Registers an EIP-1 scan on the node tracking all boxes whose script contains `pk_raw`. Returns the scan id.
+    fn register_box_scan(&self, scan_name: &str, pk_raw: &str) -> Result<u64> {
+        let json_body = json!({ "scanName": scan_name,
+                                "walletInteraction": "off",
+                                "removeOffchain": true,
+                                "trackingRule": { "predicate": "contains", "register": "R1", "value": pk_raw } });
+        let scan : ScanId = self.post("/scan/register", json_body.to_string())?.json()?;
+        Ok(scan.scan_id)
+    }
+
+    /// Gets all unspent boxes found by a registered scan
+    fn scan_unspent_boxes(&self, scan_id: u64) -> Result<Vec<NodeBox>> {
+        let scan_boxes : Vec<ScanBox> = self.get(&format!("/scan/unspentBoxes/{}", scan_id))?.json()?;
+        Ok(scan_boxes.into_iter().map(|sb| sb.ergo_box).collect())
+    }
+
+    /// Checks whether a transaction has been included in a block, is waiting in the mempool, or is unknown to the node (dropped)
+    /// Requires the node's extra indexer (`extraIndex = true`) to look up mined transactions.
+    fn tx_status(&self, tx_id: &str) -> Result<TxStatus> {
+        if let Some(res) = self.get_found(&("/blockchain/transaction/byId/".to_string() + tx_id))? {
+            let tx : IndexedTx = res.json()?;
+            return Ok(TxStatus::Confirmed(tx.inclusion_height));
+        }
+        if self.get_found(&("/transactions/unconfirmed/byTransactionId/".to_string() + tx_id))?.is_some() {
+            return Ok(TxStatus::Pending);
+        }
+        Ok(TxStatus::Dropped)
+    }
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Gets the addresses of the node wallet which belong to the network
+pub fn network_wallet_addresses(node: &dyn NodeClient, network: Network) -> Result<Vec<String>> {
+    let address_list : Vec<String> = node.wallet_addresses()?.into_iter()
+        .filter(|a| address_network(a).ok() == Some(network))
+        .collect();
+    if address_list.is_empty() {
+        return Err(ErgoCfError::Unavailable(format!("Your node wallet has no {} addresses. Please check the `network` in your config.", network)));
+    }
+    Ok(address_list)
+}
 
-    #[test]
-    fn erg_conv_is_valid() {
-        assert_eq!(1000000000, erg_to_nanoerg(1 as f64));
-        assert_eq!(erg_to_nanoerg(3.64), 3640000000);
-        assert_eq!(erg_to_nanoerg(0.64), 640000000);
-        assert_eq!(erg_to_nanoerg(0.0064), 6400000);
-        assert_eq!(erg_to_nanoerg(0.000000064), 64);
-        assert_eq!(erg_to_nanoerg(0.000000001), 1);
+/// Checks that the address is a valid address of the network which is part of the wallet `address_list`
+pub fn check_wallet_address(address: &str, address_list: &[String], network: Network) -> Result<()> {
+    validate_address(address, network)?;
+    if !address_list.iter().any(|a| a == address) {
+        return Err(ErgoCfError::InvalidCampaign(format!("The address {} is not part of your node wallet.", address)));
     }
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,334 @@
+mod common;
+
+use common::{BACKER, CREATOR, Cli, MockNode, P2S};
+use std::process::Command;
+
+#[test]
+fn create_export_delete_import_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+
+    let (code, report) = cli.run(&["create", "Test", "2000", "500", "--address", CREATOR]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["campaign"]["address"], CREATOR);
+    assert_eq!(report["campaign"]["goal_nanoerg"], 500000000000_u64);
+    assert_eq!(report["campaign"]["scan_id"], 7);
+    assert_eq!(node.requests("POST /scan/register").len(), 1);
+    assert!(cli.path("export/Test.campaign").exists());
+
+    let (code, report) = cli.run(&["export", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    let exported = std::fs::read_to_string(cli.path("export/Test.campaign")).unwrap();
+    assert!(!exported.contains("scan_id"));
+
+    let (code, _) = cli.run(&["delete", "--campaign", "Test"]);
+    assert_eq!(code, 9);
+    let (code, report) = cli.run(&["delete", "--campaign", "Test", "--yes"]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, _) = cli.run(&["info", "--campaign", "Test"]);
+    assert_eq!(code, 8);
+
+    let (code, report) = cli.run(&["import", cli.path("export/Test.campaign").to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, report) = cli.run(&["info", "--campaign", "1"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["campaign"]["name"], "Test");
+    assert_eq!(report["campaign"]["deadline"], 2000);
+}
+
+#[test]
+fn track_and_back_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+
+    let (code, report) = cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["campaign"]["deadline"], 2000);
+
+    let (code, report) = cli.run(&["back", "--campaign", "Test", "--amount", "1.5", "--address", BACKER]);
+    assert_eq!(code, 9, "{}", report);
+    assert!(node.requests("POST /wallet/transaction/send").is_empty());
+
+    let (code, report) = cli.run(&["back", "--campaign", "Test", "--amount", "1.5", "--address", BACKER, "--yes"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["backed_campaign"]["p2s_address"], P2S);
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["tx_id"], "backingtx");
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["backed_amount"], 1500000000);
+    let sent = node.requests("POST /wallet/transaction/send");
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].body.contains(&format!(r#""address":"{}""#, P2S)));
+    assert!(sent[0].body.contains(r#""value":1500000000"#));
+    assert!(sent[0].body.contains(r#""fee":1000000"#));
+
+    let (code, report) = cli.run(&["txs", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["status"]["confirmed"], 998);
+
+    node.respond("GET /blockchain/transaction/byId/", 500, r#"{"error": 500, "reason": "internal.error"}"#);
+    let (code, _) = cli.run(&["txs", "--campaign", "Test"]);
+    assert_eq!(code, 6);
+    node.respond("GET /blockchain/transaction/byId/", 404, r#"{"error": 404, "reason": "not-found"}"#);
+    let (code, report) = cli.run(&["txs", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["status"], "dropped");
+}
+
+#[test]
+fn refund_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+    let (code, report) = cli.run(&["back", "--campaign", "Test", "--amount", "1.5", "--address", BACKER, "--yes"]);
+    assert_eq!(code, 0, "{}", report);
+
+    node.respond("POST /blockchain/box/unspent/byAddress", 200, r#"[{"boxId": "box1", "value": 1500000000, "ergoTree": "1000", "transactionId": "backingtx"}]"#);
+    let (code, _) = cli.run(&["refund", "--campaign", "Test"]);
+    assert_eq!(code, 8);
+
+    node.respond("GET /info", 200, r#"{"fullHeight": 2000}"#);
+    node.respond("POST /wallet/transaction/send", 200, r#""refundtx""#);
+    let (code, report) = cli.run(&["refund", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["refund_tx_id"], "refundtx");
+    let sent = node.requests("POST /wallet/transaction/send");
+    assert_eq!(sent.len(), 2);
+    assert!(sent[1].body.contains(&format!(r#""address":"{}""#, BACKER)));
+    assert!(sent[1].body.contains(r#""value":1499000000"#));
+    assert!(sent[1].body.contains(r#""fee":1000000"#));
+    assert!(sent[1].body.contains(r#""inputsRaw":["aa"]"#));
+}
+
+#[test]
+fn collect_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    let (code, report) = cli.run(&["create", "Test", "2000", "2", "--address", CREATOR]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["campaign"]["scan_id"], 7);
+
+    let (code, _) = cli.run(&["collect", "--campaign", "Test"]);
+    assert_eq!(code, 8);
+    assert!(node.requests("POST /wallet/transaction/send").is_empty());
+
+    node.respond("GET /scan/unspentBoxes/7", 200, r#"[{"box": {"boxId": "box1", "value": 1500000000, "ergoTree": "1000", "transactionId": "tx1"}},
+                                                        {"box": {"boxId": "box2", "value": 1000000000, "ergoTree": "1000", "transactionId": "tx2"}}]"#);
+    node.respond("POST /wallet/transaction/send", 200, r#""collecttx""#);
+    let (code, report) = cli.run(&["collect", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["campaign"]["collect_tx_id"], "collecttx");
+    let sent = node.requests("POST /wallet/transaction/send");
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].body.contains(&format!(r#""address":"{}""#, CREATOR)));
+    assert!(sent[0].body.contains(r#""value":2499000000"#));
+    assert!(sent[0].body.contains(r#""fee":1000000"#));
+    assert!(sent[0].body.contains(r#""inputsRaw":["aa","aa"]"#));
+}
+
+#[test]
+fn status_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    cli.run(&["track", "Tracked", CREATOR, "2000", "2"]);
+    let (code, _) = cli.run(&["status", "--campaign", "Tracked"]);
+    assert_eq!(code, 8);
+
+    cli.run(&["create", "Test", "2000", "2", "--address", CREATOR]);
+    node.respond("GET /scan/unspentBoxes/7", 200, r#"[{"box": {"boxId": "box1", "value": 1500000000, "ergoTree": "1000", "transactionId": "tx1"}}]"#);
+    let (code, report) = cli.run(&["status", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["status"]["raised"], 1500000000);
+    assert_eq!(report["status"]["blocks_remaining"], 1000);
+    assert_eq!(report["status"]["state"], "active");
+
+    node.respond("GET /info", 200, r#"{"fullHeight": 2000}"#);
+    let (code, report) = cli.run(&["status", "--campaign", "Test"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["status"]["state"], "failed");
+}
+
+#[test]
+fn back_checks_are_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+
+    let (code, _) = cli.run(&["back", "--campaign", "Test", "--amount", "0", "--address", BACKER, "--yes"]);
+    assert_eq!(code, 2);
+    let (code, _) = cli.run(&["back", "--campaign", "Test", "--amount", "10", "--address", BACKER, "--yes"]);
+    assert_eq!(code, 8);
+    node.respond("GET /info", 200, r#"{"fullHeight": 2000}"#);
+    let (code, _) = cli.run(&["back", "--campaign", "Test", "--amount", "1", "--address", BACKER, "--yes"]);
+    assert_eq!(code, 8);
+    assert!(node.requests("POST /wallet/transaction/send").is_empty());
+
+    let (code, _) = cli.run(&["track", "Late", CREATOR, "1990", "500"]);
+    assert_eq!(code, 8);
+    let (code, report) = cli.run(&["track", "Late", CREATOR, "1990", "500", "--force"]);
+    assert_eq!(code, 0, "{}", report);
+}
+
+#[test]
+fn node_errors_are_reported() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+
+    node.respond("GET /info", 401, r#"{"error": 401, "reason": "Unauthorized"}"#);
+    let (code, report) = cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+    assert_eq!(code, 4);
+    assert_eq!(report["error"]["exit_code"], 4);
+
+    node.respond("GET /info", 200, "not json");
+    let (code, _) = cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+    assert_eq!(code, 6);
+
+    node.respond("GET /wallet/addresses", 400, r#"{"error": 400, "reason": "bad.request", "detail": "Wallet is locked"}"#);
+    let (code, _) = cli.run(&["create", "Test", "2000", "500"]);
+    assert_eq!(code, 5);
+
+    node.respond("GET /wallet/addresses", 500, r#"{"error": 500, "reason": "internal.error"}"#);
+    let (code, report) = cli.run(&["create", "Test", "2000", "500"]);
+    assert_eq!(code, 6);
+    assert!(report["error"]["message"].as_str().unwrap().contains("internal.error"));
+
+    let (code, _) = Cli::new("http://127.0.0.1:1").run(&["track", "Test", CREATOR, "2000", "500"]);
+    assert_eq!(code, 3);
+}
+
+#[test]
+fn record_and_replay_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    let track_fixture = cli.path("track.json");
+    let back_fixture = cli.path("back.json");
+    let (code, report) = cli.run(&["track", "Test", CREATOR, "2000", "500", "--record", track_fixture.to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, report) = cli.run(&["back", "--campaign", "Test", "--amount", "1", "--address", BACKER, "--yes", "--record", back_fixture.to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    let recorded = std::fs::read_to_string(&back_fixture).unwrap();
+    assert!(recorded.contains(r#""api_key": "********""#));
+    assert!(!recorded.contains("hello"));
+    drop(node);
+
+    let offline = Cli::new("http://127.0.0.1:1");
+    let (code, report) = offline.run(&["track", "Test", CREATOR, "2000", "500", "--replay", track_fixture.to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, report) = offline.run(&["back", "--campaign", "Test", "--amount", "1", "--address", BACKER, "--yes", "--replay", back_fixture.to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["backed_campaign"]["backer_txs"][0]["tx_id"], "backingtx");
+    let (code, _) = offline.run(&["txs", "--campaign", "Test", "--replay", back_fixture.to_str().unwrap()]);
+    assert_eq!(code, 6);
+}
+
+#[test]
+fn data_move_is_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+
+    let (code, report) = cli.run(&["track", "Test", CREATOR, "2000", "500"]);
+    assert_eq!(code, 0, "{}", report);
+
+    let moved = cli.path("moved");
+    let moved = moved.to_str().unwrap();
+    let (code, report) = cli.run(&["data", "move", moved]);
+    assert_eq!(code, 0, "{}", report);
+    assert!(report["messages"][1].as_str().unwrap().contains("ERGO_CF_DATA_DIR"), "{}", report);
+    assert!(cli.path("moved/.storage/campaigns.db").exists());
+    assert!(!cli.path(".storage").exists());
+    let config = std::fs::read_to_string(cli.path("ergo_cf.toml")).unwrap();
+    assert!(config.contains(&format!("data_dir = '{}'", moved)), "{}", config);
+
+    let (code, report) = cli.run(&["data", "show", "--data-dir", moved]);
+    assert_eq!(code, 0, "{}", report);
+    assert!(report["messages"][0].as_str().unwrap().contains(moved));
+    let (code, report) = cli.run(&["info", "--campaign", "Test", "--data-dir", moved]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, _) = cli.run(&["data", "move", moved]);
+    assert_eq!(code, 8);
+    let missing = cli.path("missing");
+    let other = cli.path("other");
+    let (code, report) = cli.run(&["data", "move", other.to_str().unwrap(), "--data-dir", missing.to_str().unwrap()]);
+    assert_eq!(code, 0, "{}", report);
+    let (code, _) = cli.run(&["data", "move", missing.to_str().unwrap(), "--data-dir", missing.to_str().unwrap()]);
+    assert_eq!(code, 8);
+}
+
+#[test]
+fn legacy_storage_is_kept() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    std::fs::create_dir_all(cli.path(".storage/campaigns")).unwrap();
+
+    let (code, report) = cli.run(&["data", "show"]);
+    assert_eq!(code, 0, "{}", report);
+    let dir = cli.path("").canonicalize().unwrap();
+    let config = std::fs::read_to_string(cli.path("ergo_cf.toml")).unwrap();
+    assert!(config.contains(&format!("data_dir = '{}'", dir.to_str().unwrap())), "{}", config);
+}
+
+#[test]
+fn config_commands_are_valid() {
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+
+    let (code, report) = cli.run(&["config", "get", "colour"]);
+    assert_eq!(code, 11);
+    assert_eq!(report["error"]["exit_code"], 11);
+    let (code, _) = cli.run(&["status", "--profile", "missing"]);
+    assert_eq!(code, 11);
+
+    let (code, report) = cli.run(&["config", "get", "api_key"]);
+    assert_eq!(code, 0, "{}", report);
+    assert_eq!(report["messages"][0], "********");
+
+    let (code, _) = cli.run(&["config", "set", "api_key", "secret"]);
+    assert_eq!(code, 9);
+    let (code, report) = cli.run_with_env(&["config", "set", "api_key", "secret"], &[("ERGO_CF_PASSPHRASE", "passphrase")]);
+    assert_eq!(code, 0, "{}", report);
+    let config = std::fs::read_to_string(cli.path("ergo_cf.toml")).unwrap();
+    assert!(config.contains("api_key_encrypted"), "{}", config);
+    assert!(!config.contains("secret"), "{}", config);
+
+    std::fs::write(cli.path("ergo_cf.toml"), "api_key = 'plainkey'\n\n[profiles.office]\nnode_url = 'http://10.0.0.2:9052'\nnetwork = 'mainnet'\napi_key_encrypted = 'blob'\n").unwrap();
+    for args in [&["config", "show"][..], &["profile", "list"][..]] {
+        let (code, report) = cli.run(args);
+        assert_eq!(code, 0, "{}", report);
+        assert!(report["messages"].to_string().contains("saved in plaintext"), "{}", report);
+        assert!(!report.to_string().contains("blob"), "{}", report);
+    }
+
+    let (code, report) = cli.run(&["config", "set", "node_url", "http://10.0.0.3:9052/", "--profile", "office"]);
+    assert_eq!(code, 0, "{}", report);
+    assert!(report["messages"].to_string().contains("`node_url` has been set to `http://10.0.0.3:9052`."), "{}", report);
+    let (code, report) = cli.run(&["config", "set", "fee", "0.002", "--profile", "office"]);
+    assert_eq!(code, 0, "{}", report);
+    let config = std::fs::read_to_string(cli.path("ergo_cf.toml")).unwrap();
+    assert!(config.contains("[profiles.office]\nnode_url = 'http://10.0.0.3:9052'"), "{}", config);
+    assert!(config.starts_with("node_url = 'http://0.0.0.0:9052'"), "{}", config);
+    assert!(config.contains("fee = 2000000"), "{}", config);
+    let (code, _) = cli.run(&["config", "set", "network", "mainnet", "--profile", "missing"]);
+    assert_eq!(code, 11);
+}
+
+#[test]
+fn unknown_output_format_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ergo_cf")).args(["info", "--output", "jsn"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`jsn` is not a valid output format"));
+}
+
+#[test]
+fn fee_below_minimum_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ergo_cf")).args(["info", "--fee", "0.0005"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("below the minimum fee of 0.001 Erg"));
+
+    let node = MockNode::start();
+    let cli = Cli::new(&node.url);
+    let (code, _) = cli.run(&["config", "set", "fee", "0.0005"]);
+    assert_eq!(code, 11);
+    std::fs::write(cli.path("ergo_cf.toml"), "fee = 500000\n").unwrap();
+    let (code, report) = cli.run(&["refund", "--campaign", "Test"]);
+    assert_eq!(code, 11, "{}", report);
+    let (code, report) = cli.run(&["config", "set", "fee", "0.002"]);
+    assert_eq!(code, 0, "{}", report);
+}
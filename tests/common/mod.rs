@@ -0,0 +1,141 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tempfile::TempDir;
+use tiny_http::{Response, Server};
+
+pub const CREATOR : &str = "9eeaTe6SajGaPya1cj7L1h4T4WC7tvje4wk7h87k8i4R9kkngdf";
+pub const BACKER : &str = "9giqagqgA7ix97nmFUhrty2smh7gisuEudHZgkT6o1QAUiRauCe";
+pub const P2S : &str = "nA46m9Zz6DsA4yAPj7E9MVDU5BcdZFvGJq6RooKfk8yisehVHtX2QtjtjXCzsJmQZDTJRZ8DtscG7T8tm67Zhf94atLDoeBXKFUEYDce3gxKgu8Fpn9ZbpoqdcqWFfS";
+
+/// A request received by the `MockNode`
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: String,
+    pub url: String,
+    pub body: String,
+}
+
+/// Ergo node api served in-process on localhost, answering every request with a scripted response.
+pub struct MockNode {
+    pub url: String,
+    server: Arc<Server>,
+    responses: Arc<Mutex<HashMap<String, (u16, String)>>>,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl MockNode {
+    /// Starts a `MockNode` at height 1000 whose unlocked wallet holds the `CREATOR` and `BACKER` addresses and 10 Erg
+    pub fn start() -> MockNode {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("Failed to start the mock node"));
+        let url = format!("http://{}", server.server_addr().to_ip().expect("Mock node is not listening on an ip address"));
+        let responses = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let handle = {
+            let (server, responses, requests) = (server.clone(), responses.clone(), requests.clone());
+            thread::spawn(move || serve(&server, &responses, &requests))
+        };
+        let node = MockNode { url, server, responses, requests, handle: Some(handle) };
+        node.respond("GET /info", 200, r#"{"name": "mock", "fullHeight": 1000}"#);
+        node.respond("GET /wallet/addresses", 200, &format!(r#"["{}", "{}"]"#, CREATOR, BACKER));
+        node.respond("GET /wallet/balances", 200, r#"{"height": 1000, "balance": 10000000000, "assets": {}}"#);
+        node.respond("POST /script/p2sAddress", 200, &format!(r#"{{"address": "{}"}}"#, P2S));
+        node.respond("POST /wallet/transaction/send", 200, r#""backingtx""#);
+        node.respond("GET /utils/addressToRaw/", 200, r#"{"raw": "02aa"}"#);
+        node.respond("GET /script/addressToTree/", 200, r#"{"tree": "1000"}"#);
+        node.respond("POST /scan/register", 200, r#"{"scanId": 7}"#);
+        node.respond("GET /scan/unspentBoxes/", 200, "[]");
+        node.respond("POST /blockchain/box/unspent/byAddress", 200, "[]");
+        node.respond("GET /utxo/byIdBinary/", 200, r#"{"boxId": "box", "bytes": "aa"}"#);
+        node.respond("GET /blockchain/transaction/byId/", 200, r#"{"inclusionHeight": 998}"#);
+        node
+    }
+
+    /// Scripts the response to requests matching `route`, a method followed by a path or the start of one (e.g. `GET /utils/addressToRaw/`)
+    pub fn respond(&self, route: &str, status: u16, body: &str) {
+        self.responses.lock().unwrap().insert(route.to_string(), (status, body.to_string()));
+    }
+
+    /// Every request received matching the `route`
+    pub fn requests(&self, route: &str) -> Vec<ReceivedRequest> {
+        self.requests.lock().unwrap().iter()
+            .filter(|r| format!("{} {}", r.method, r.url).starts_with(route))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Answers requests with the response of the longest matching route, or a 404 like the node does for unknown paths
+fn serve(server: &Server, responses: &Mutex<HashMap<String, (u16, String)>>, requests: &Mutex<Vec<ReceivedRequest>>) {
+    while let Ok(mut request) = server.recv() {
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).ok();
+        let received = ReceivedRequest { method: request.method().to_string(), url: request.url().to_string(), body };
+        let key = format!("{} {}", received.method, received.url);
+        requests.lock().unwrap().push(received);
+
+        let (status, body) = responses.lock().unwrap().iter()
+            .filter(|(route, _)| key.starts_with(route.as_str()))
+            .max_by_key(|(route, _)| route.len())
+            .map(|(_, response)| response.clone())
+            .unwrap_or((404, r#"{"error": 404, "reason": "not-found", "detail": "The requested resource could not be found"}"#.to_string()));
+        request.respond(Response::from_string(body).with_status_code(status)).ok();
+    }
+}
+
+/// Runs the `ergo_cf` binary inside of a temporary data dir, against the node at `node_url`
+pub struct Cli {
+    dir: TempDir,
+    node_url: String,
+}
+
+impl Cli {
+    pub fn new(node_url: &str) -> Cli {
+        Cli { dir: TempDir::new().expect("Failed to create a temporary dir"), node_url: node_url.to_string() }
+    }
+
+    /// Path of a file inside of the data dir
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.dir.path().join(file)
+    }
+
+    /// Runs the command with `--output json`. Returns the exit code and the json document.
+    pub fn run(&self, args: &[&str]) -> (i32, Value) {
+        self.run_with_env(args, &[])
+    }
+
+    /// Runs the command with `--output json` and the extra environment variables
+    pub fn run_with_env(&self, args: &[&str], vars: &[(&str, &str)]) -> (i32, Value) {
+        let dir : &Path = self.dir.path();
+        let output = Command::new(env!("CARGO_BIN_EXE_ergo_cf"))
+            .args(args)
+            .args(["--output", "json"])
+            .current_dir(dir)
+            .env("ERGO_CF_CONFIG", dir.join("ergo_cf.toml"))
+            .env("ERGO_CF_NODE_URL", &self.node_url)
+            .env("ERGO_CF_API_KEY", "hello")
+            .env("ERGO_CF_DATA_DIR", dir)
+            .env_remove("ERGO_CF_PROFILE")
+            .env_remove("ERGO_CF_PASSPHRASE")
+            .envs(vars.iter().copied())
+            .output()
+            .expect("Failed to run ergo_cf");
+        let report = serde_json::from_slice(&output.stdout)
+            .unwrap_or_else(|e| panic!("ergo_cf printed no json document ({}): {}", e, String::from_utf8_lossy(&output.stdout)));
+        (output.status.code().unwrap_or(-1), report)
+    }
+}